@@ -19,7 +19,7 @@ fn load_config() -> graze::Result<Config, toml::de::Error> {
     graze::load_or_write_default(
         "Config.toml",
         |s| toml::from_str(s),
-        |c| toml::to_string(c).unwrap(),
+        |c| toml::to_string(c).map_err(serde::de::Error::custom),
         Config::default,
     )
 }
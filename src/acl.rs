@@ -0,0 +1,88 @@
+//! Restricting the access control list of written configuration files on Windows, backed by
+//! [windows_sys].
+
+use std::io;
+use std::mem;
+use std::os::windows::ffi::OsStrExt;
+use std::path::Path;
+use std::ptr;
+
+use windows_sys::Win32::Foundation::{CloseHandle, ERROR_SUCCESS, HANDLE};
+use windows_sys::Win32::Security::Authorization::{SetNamedSecurityInfoW, SE_FILE_OBJECT};
+use windows_sys::Win32::Security::{
+    AddAccessAllowedAce, GetLengthSid, GetTokenInformation, InitializeAcl, TokenUser,
+    ACCESS_ALLOWED_ACE, ACL, ACL_REVISION, DACL_SECURITY_INFORMATION,
+    PROTECTED_DACL_SECURITY_INFORMATION, TOKEN_QUERY, TOKEN_USER,
+};
+use windows_sys::Win32::Storage::FileSystem::FILE_ALL_ACCESS;
+use windows_sys::Win32::System::Threading::{GetCurrentProcess, OpenProcessToken};
+
+/// Replace the access control list of the file at `path` with one granting full access to the
+/// current user only.
+///
+/// The new list is protected, so the file no longer inherits the entries of its directory's list.
+pub(crate) fn restrict_to_owner(path: &Path) -> io::Result<()> {
+    let user = current_user()?;
+    // SAFETY: `user` holds a `TOKEN_USER`, whose SID points into the same buffer.
+    let sid = unsafe { (*user.as_ptr().cast::<TOKEN_USER>()).User.Sid };
+
+    // SAFETY: `sid` is a valid SID, and `acl` is large enough for the header and a single entry.
+    unsafe {
+        let size = mem::size_of::<ACL>() + mem::size_of::<ACCESS_ALLOWED_ACE>()
+            - mem::size_of::<u32>()
+            + GetLengthSid(sid) as usize;
+        let mut acl = vec![0u32; size.div_ceil(mem::size_of::<u32>())];
+        let acl = acl.as_mut_ptr().cast::<ACL>();
+
+        if InitializeAcl(acl, size as u32, ACL_REVISION) == 0
+            || AddAccessAllowedAce(acl, ACL_REVISION, FILE_ALL_ACCESS, sid) == 0
+        {
+            return Err(io::Error::last_os_error());
+        }
+
+        let mut wide: Vec<u16> = path.as_os_str().encode_wide().chain([0]).collect();
+        let result = SetNamedSecurityInfoW(
+            wide.as_mut_ptr(),
+            SE_FILE_OBJECT,
+            DACL_SECURITY_INFORMATION | PROTECTED_DACL_SECURITY_INFORMATION,
+            ptr::null_mut(),
+            ptr::null_mut(),
+            acl,
+            ptr::null(),
+        );
+
+        if result != ERROR_SUCCESS {
+            return Err(io::Error::from_raw_os_error(result as i32));
+        }
+    }
+
+    Ok(())
+}
+
+/// Get the `TOKEN_USER` of the current process, describing the user it runs as.
+///
+/// The buffer is made of `u64`s to keep the `TOKEN_USER` at its start suitably aligned.
+fn current_user() -> io::Result<Vec<u64>> {
+    let mut token: HANDLE = ptr::null_mut();
+
+    // SAFETY: `token` is closed before returning, and `user` is at least `len` bytes long.
+    unsafe {
+        if OpenProcessToken(GetCurrentProcess(), TOKEN_QUERY, &mut token) == 0 {
+            return Err(io::Error::last_os_error());
+        }
+
+        let mut len = 0;
+        GetTokenInformation(token, TokenUser, ptr::null_mut(), 0, &mut len);
+
+        let mut user = vec![0u64; (len as usize).div_ceil(mem::size_of::<u64>())];
+        let ok = GetTokenInformation(token, TokenUser, user.as_mut_ptr().cast(), len, &mut len);
+        let result = if ok == 0 {
+            Err(io::Error::last_os_error())
+        } else {
+            Ok(user)
+        };
+
+        CloseHandle(token);
+        result
+    }
+}
@@ -0,0 +1,202 @@
+//! Asynchronous variants of the loading functions, backed by [tokio].
+//!
+//! The deserializer and serializer closures remain synchronous, as they are CPU-bound; only the
+//! filesystem access is performed asynchronously.
+
+use std::io;
+use std::path::{Path, PathBuf};
+
+use tokio::fs;
+use tokio::io::AsyncWriteExt;
+
+use crate::{
+    decode_content, load_from_str, read_error, temp_sibling, ConfigurationError, Result,
+    TEMP_ATTEMPTS,
+};
+
+/// Asynchronously load a configuration from the file at the given path.
+///
+/// See [load_from_path](crate::load_from_path) for details.
+///
+/// # Examples
+///
+/// ```no_run
+/// use serde::Deserialize;
+///
+/// #[derive(Deserialize)]
+/// struct Config {
+///     message: String
+/// }
+///
+/// # async fn run() {
+/// let config: Config = graze::load_from_path_async("Config.toml", |c| toml::from_str(c))
+///     .await
+///     .expect("Could not load configuration");
+/// # }
+/// ```
+pub async fn load_from_path_async<P, T, E, D>(path: P, deserializer: D) -> Result<T, E>
+where
+    P: AsRef<Path>,
+    D: FnOnce(&str) -> std::result::Result<T, E>,
+{
+    let content = read_to_string(path.as_ref()).await?;
+
+    deserializer(&content).map_err(ConfigurationError::deserialize)
+}
+
+/// Asynchronously load a configuration from the file at the given path, or use the default value
+/// if the file does not exist.
+///
+/// See [load_or_default](crate::load_or_default) for details.
+pub async fn load_or_default_async<P, T, E, D, F>(
+    path: P,
+    deserializer: D,
+    default: F,
+) -> Result<T, E>
+where
+    P: AsRef<Path>,
+    D: FnOnce(&str) -> std::result::Result<T, E>,
+    F: FnOnce() -> T,
+{
+    match read_if_exists(path.as_ref()).await? {
+        Some(content) => load_from_str(&content, deserializer),
+        None => Ok(default()),
+    }
+}
+
+/// Asynchronously load a configuration from the file at the given path, or use the default value
+/// if the file does not exist.
+///
+/// If the file does not exist, the default value will be written atomically to the file at the
+/// given path, creating any missing parent directories. An existing file is never replaced. See
+/// [load_or_write_default](crate::load_or_write_default) for details.
+pub async fn load_or_write_default_async<P, T, E, D, S, F, B>(
+    path: P,
+    deserializer: D,
+    serializer: S,
+    default: F,
+) -> Result<T, E>
+where
+    P: AsRef<Path>,
+    D: FnOnce(&str) -> std::result::Result<T, E>,
+    S: FnOnce(&T) -> std::result::Result<B, E>,
+    B: AsRef<[u8]>,
+    F: FnOnce() -> T,
+{
+    let path = path.as_ref();
+
+    if let Some(content) = read_if_exists(path).await? {
+        return load_from_str(&content, deserializer);
+    }
+
+    let data = default();
+    let serialized = serializer(&data).map_err(ConfigurationError::serialize)?;
+
+    if let Some(parent) = path.parent() {
+        fs::create_dir_all(parent)
+            .await
+            .map_err(|err| ConfigurationError::io(path, err))?;
+    }
+
+    match write_new_atomic(path, serialized.as_ref()).await {
+        Err(err) if err.kind() == io::ErrorKind::AlreadyExists => {
+            return load_from_path_async(path, deserializer).await;
+        }
+        result => result.map_err(|err| ConfigurationError::io(path, err))?,
+    }
+
+    Ok(data)
+}
+
+/// Asynchronously read the file at `path`, reporting a missing file as
+/// [ConfigurationError::NotFound] and a directory as [ConfigurationError::IsDirectory], as the
+/// synchronous loading functions do.
+///
+/// With the `gzip` feature, a gzip compressed file is decompressed.
+async fn read_to_string<E>(path: &Path) -> Result<String, E> {
+    if fs::metadata(path)
+        .await
+        .is_ok_and(|metadata| metadata.is_dir())
+    {
+        return Err(ConfigurationError::IsDirectory {
+            path: path.to_path_buf(),
+        });
+    }
+
+    let content = fs::read(path).await.map_err(|err| read_error(path, err))?;
+    decode_content(path, content)
+}
+
+/// Asynchronously read the file at `path`, returning [None] if it does not exist.
+///
+/// Only a missing file counts as the file not existing; any other error (e.g. the path being a
+/// directory or unreadable) is returned.
+async fn read_if_exists<E>(path: &Path) -> Result<Option<String>, E> {
+    match read_to_string(path).await {
+        Ok(content) => Ok(Some(content)),
+        Err(ConfigurationError::NotFound { .. }) => Ok(None),
+        Err(err) => Err(err),
+    }
+}
+
+/// Asynchronously write `contents` to `path` unless it already exists, in which case an
+/// [io::ErrorKind::AlreadyExists] error is returned.
+///
+/// This is the asynchronous counterpart of the writer used by
+/// [load_or_write_default](crate::load_or_write_default): the content is written to a temporary
+/// sibling of `path`, which is then hard linked to `path`, falling back to creating the file in
+/// place if the link cannot be created.
+async fn write_new_atomic(path: &Path, contents: &[u8]) -> io::Result<()> {
+    #[cfg(feature = "gzip")]
+    let contents = &*crate::gzip::compress_for(path, contents)?;
+
+    let (mut file, tmp) = create_temp(path).await?;
+
+    let mut result = file.write_all(contents).await;
+    if result.is_ok() {
+        result = file.flush().await;
+    }
+    drop(file);
+    if result.is_ok() {
+        result = fs::hard_link(&tmp, path).await;
+    }
+    let _ = fs::remove_file(&tmp).await;
+
+    match result {
+        Err(err) if err.kind() != io::ErrorKind::AlreadyExists => {
+            let mut file = fs::OpenOptions::new()
+                .write(true)
+                .create_new(true)
+                .open(path)
+                .await?;
+            file.write_all(contents).await
+        }
+        result => result,
+    }
+}
+
+/// Asynchronously create a new temporary sibling of `path` for writing, returning the file and its
+/// path.
+///
+/// This is the asynchronous counterpart of the function used by
+/// [load_or_write_default](crate::load_or_write_default): an existing file or symbolic link is
+/// never reused, and the next name is tried instead.
+async fn create_temp(path: &Path) -> io::Result<(fs::File, PathBuf)> {
+    let mut attempt = 0;
+
+    loop {
+        let tmp = temp_sibling(path, attempt);
+
+        match fs::OpenOptions::new()
+            .write(true)
+            .create_new(true)
+            .open(&tmp)
+            .await
+        {
+            Err(err) if err.kind() == io::ErrorKind::AlreadyExists && attempt < TEMP_ATTEMPTS => {
+                attempt += 1
+            }
+            result => return result.map(|file| (file, tmp)),
+        }
+    }
+}
@@ -0,0 +1,103 @@
+//! Variants of the loading functions which erase the type of the deserializer error.
+
+use std::error::Error;
+use std::path::Path;
+
+use crate::{load_from_path, load_or_default, load_or_write_default, ConfigurationError, Result};
+
+/// A boxed deserializer or serializer error, as returned by the functions ending in `_boxed`.
+pub type BoxError = Box<dyn Error + Send + Sync>;
+
+impl<E> ConfigurationError<E>
+where
+    E: Error + Send + Sync + 'static,
+{
+    /// Box the deserializer or serializer error, erasing its type.
+    ///
+    /// This allows errors from loading configurations in different formats to be stored together.
+    pub fn into_boxed(self) -> ConfigurationError<BoxError> {
+        self.map_deserialize(|err| Box::new(err) as BoxError)
+    }
+}
+
+/// Load a configuration from the file at the given path, boxing the deserializer error.
+///
+/// This is useful for storing loaders of configurations in different formats together, as their
+/// errors all have the same type. See [load_from_path] for details.
+///
+/// # Examples
+///
+/// ```no_run
+/// use graze::{BoxError, ConfigurationError};
+/// use serde::Deserialize;
+///
+/// #[derive(Deserialize)]
+/// struct Config {
+///     message: String
+/// }
+///
+/// type Loader = Box<dyn Fn() -> Result<Config, ConfigurationError<BoxError>>>;
+///
+/// let loaders: Vec<Loader> = vec![
+///     Box::new(|| graze::load_boxed("Config.toml", |c| toml::from_str(c))),
+///     Box::new(|| {
+///         graze::load_boxed("Message.txt", |c| {
+///             Ok::<_, std::io::Error>(Config { message: c.trim().to_string() })
+///         })
+///     }),
+/// ];
+///
+/// for loader in &loaders {
+///     if let Ok(config) = loader() {
+///         println!("{}", config.message);
+///     }
+/// }
+/// ```
+pub fn load_boxed<P, T, E, D>(path: P, deserializer: D) -> Result<T, BoxError>
+where
+    P: AsRef<Path>,
+    E: Error + Send + Sync + 'static,
+    D: FnOnce(&str) -> std::result::Result<T, E>,
+{
+    load_from_path(path, deserializer).map_err(ConfigurationError::into_boxed)
+}
+
+/// Load a configuration from the file at the given path, or use the default value if the file does
+/// not exist, boxing the deserializer error.
+///
+/// See [load_or_default] for details.
+pub fn load_or_default_boxed<P, T, E, D, F>(
+    path: P,
+    deserializer: D,
+    default: F,
+) -> Result<T, BoxError>
+where
+    P: AsRef<Path>,
+    E: Error + Send + Sync + 'static,
+    D: FnOnce(&str) -> std::result::Result<T, E>,
+    F: FnOnce() -> T,
+{
+    load_or_default(path, deserializer, default).map_err(ConfigurationError::into_boxed)
+}
+
+/// Load a configuration from the file at the given path, or write and use the default value if the
+/// file does not exist, boxing the deserializer and serializer errors.
+///
+/// See [load_or_write_default] for details.
+pub fn load_or_write_default_boxed<P, T, E, D, S, F, B>(
+    path: P,
+    deserializer: D,
+    serializer: S,
+    default: F,
+) -> Result<T, BoxError>
+where
+    P: AsRef<Path>,
+    E: Error + Send + Sync + 'static,
+    D: FnOnce(&str) -> std::result::Result<T, E>,
+    S: FnOnce(&T) -> std::result::Result<B, E>,
+    B: AsRef<[u8]>,
+    F: FnOnce() -> T,
+{
+    load_or_write_default(path, deserializer, serializer, default)
+        .map_err(ConfigurationError::into_boxed)
+}
@@ -0,0 +1,112 @@
+//! Caching a loaded configuration until its file is modified.
+
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::time::SystemTime;
+
+use crate::{load_from_str, read_error, read_to_string, ConfigurationError, Result};
+
+type Deserializer<T, E> = Box<dyn Fn(&str) -> std::result::Result<T, E>>;
+
+/// A loaded configuration which is only reloaded once its file is modified.
+///
+/// Each call to [CachedConfig::get] checks the modification time of the file, and only reads and
+/// deserializes the file again if the modification time changed since the configuration was last
+/// loaded. This makes it cheap to get the configuration in hot paths, such as for every request
+/// handled by a server, while still picking up changes to the file.
+///
+/// # Examples
+///
+/// ```no_run
+/// use graze::CachedConfig;
+/// use serde::Deserialize;
+///
+/// #[derive(Deserialize)]
+/// struct Config {
+///     message: String
+/// }
+///
+/// let mut cached = CachedConfig::load("Config.toml", |c| toml::from_str::<Config>(c))
+///     .expect("Could not load configuration");
+///
+/// loop {
+///     let (config, err) = cached.get();
+///
+///     if let Some(err) = err {
+///         eprintln!("Could not reload configuration: {err}");
+///     }
+///     println!("{}", config.message);
+/// #   break;
+/// }
+/// ```
+pub struct CachedConfig<T, E> {
+    value: T,
+    path: PathBuf,
+    modified: SystemTime,
+    deserializer: Deserializer<T, E>,
+}
+
+impl<T, E> CachedConfig<T, E> {
+    /// Load the configuration from the file at the given path.
+    ///
+    /// See [load_from_path](crate::load_from_path) for details.
+    pub fn load<P, D>(path: P, deserializer: D) -> Result<Self, E>
+    where
+        P: AsRef<Path>,
+        D: Fn(&str) -> std::result::Result<T, E> + 'static,
+    {
+        let path = path.as_ref().to_path_buf();
+        let modified = modified(&path)?;
+        let value = load_from_str(&read_to_string(&path)?, &deserializer)?;
+
+        Ok(Self {
+            value,
+            path,
+            modified,
+            deserializer: Box::new(deserializer),
+        })
+    }
+
+    /// Get the configuration, reloading it first if its file was modified since it was last loaded.
+    ///
+    /// If the modification time of the file cannot be read, or reloading the file fails, the
+    /// previously loaded configuration is returned along with the error. A file which failed to
+    /// reload is read again by the next call, even if it was not modified in the meantime.
+    pub fn get(&mut self) -> (&T, Option<ConfigurationError<E>>) {
+        let err = self.reload_if_modified().err();
+        (&self.value, err)
+    }
+
+    /// Get the last loaded configuration, without checking whether its file was modified.
+    pub fn cached(&self) -> &T {
+        &self.value
+    }
+
+    /// Get the path of the configuration file.
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+
+    /// Get the configuration, discarding the path and deserializer.
+    pub fn into_inner(self) -> T {
+        self.value
+    }
+
+    /// Reload the configuration if the modification time of its file changed.
+    fn reload_if_modified(&mut self) -> Result<(), E> {
+        let modified = modified(&self.path)?;
+
+        if modified != self.modified {
+            self.value = load_from_str(&read_to_string(&self.path)?, &self.deserializer)?;
+            self.modified = modified;
+        }
+        Ok(())
+    }
+}
+
+/// Get the modification time of the file at `path`.
+fn modified<E>(path: &Path) -> Result<SystemTime, E> {
+    fs::metadata(path)
+        .and_then(|metadata| metadata.modified())
+        .map_err(|err| read_error(path, err))
+}
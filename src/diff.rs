@@ -0,0 +1,196 @@
+//! Comparing configurations.
+
+use std::path::Path;
+
+use crate::{load_from_path, Result};
+
+/// A difference between two serialized configurations, found by [diff_serialized].
+///
+/// Line numbers start at 1.
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub enum LineDiff {
+    /// A line only present in the new configuration.
+    Added {
+        /// The number of the line in the new configuration.
+        new_line: usize,
+
+        /// The content of the line.
+        content: String,
+    },
+
+    /// A line only present in the old configuration.
+    Removed {
+        /// The number of the line in the old configuration.
+        old_line: usize,
+
+        /// The content of the line.
+        content: String,
+    },
+
+    /// A line of the old configuration which was replaced by a line of the new configuration.
+    Changed {
+        /// The number of the line in the old configuration.
+        old_line: usize,
+
+        /// The number of the line in the new configuration.
+        new_line: usize,
+
+        /// The content of the line in the old configuration.
+        old: String,
+
+        /// The content of the line in the new configuration.
+        new: String,
+    },
+}
+
+/// Compare two serialized configurations line by line.
+///
+/// The lines which are not part of a longest common subsequence of the two configurations are
+/// returned in order. Within each run of differing lines, removed and added lines are paired up as
+/// [LineDiff::Changed]; the remaining lines are returned as [LineDiff::Removed] or
+/// [LineDiff::Added]. As the configurations are compared as text, this works with any format, but
+/// reordered lines are reported as differences.
+///
+/// # Examples
+///
+/// ```
+/// use graze::LineDiff;
+///
+/// let old = "name = 'graze'\nrange = 1\n";
+/// let new = "name = 'graze'\nrange = 2\n";
+///
+/// assert_eq!(
+///     graze::diff_serialized(old, new),
+///     [LineDiff::Changed {
+///         old_line: 2,
+///         new_line: 2,
+///         old: "range = 1".to_string(),
+///         new: "range = 2".to_string(),
+///     }]
+/// );
+/// ```
+pub fn diff_serialized(old: &str, new: &str) -> Vec<LineDiff> {
+    let old: Vec<_> = old.lines().collect();
+    let new: Vec<_> = new.lines().collect();
+
+    // common[i][j] is the length of the longest common subsequence of old[i..] and new[j..].
+    let mut common = vec![vec![0; new.len() + 1]; old.len() + 1];
+    for i in (0..old.len()).rev() {
+        for j in (0..new.len()).rev() {
+            common[i][j] = if old[i] == new[j] {
+                common[i + 1][j + 1] + 1
+            } else {
+                common[i + 1][j].max(common[i][j + 1])
+            };
+        }
+    }
+
+    let mut diffs = Vec::new();
+    let (mut removed, mut added) = (Vec::new(), Vec::new());
+    let (mut i, mut j) = (0, 0);
+
+    while i < old.len() || j < new.len() {
+        if i < old.len() && j < new.len() && old[i] == new[j] {
+            flush(&mut diffs, &mut removed, &mut added);
+            i += 1;
+            j += 1;
+        } else if j == new.len() || (i < old.len() && common[i + 1][j] >= common[i][j + 1]) {
+            removed.push((i + 1, old[i]));
+            i += 1;
+        } else {
+            added.push((j + 1, new[j]));
+            j += 1;
+        }
+    }
+    flush(&mut diffs, &mut removed, &mut added);
+
+    diffs
+}
+
+/// Load a configuration from the file at the given path and report how it differs from the default
+/// value.
+///
+/// `compare` is called with the loaded configuration and the default value, in that order, and
+/// returns the names of the fields which differ; since a configuration cannot be inspected field by
+/// field in general, comparing them is left to the caller. This is useful for e.g. a command which
+/// shows which settings were changed from their defaults. See [load_from_path] for details.
+///
+/// # Examples
+///
+/// ```no_run
+/// use serde::Deserialize;
+///
+/// #[derive(Deserialize, Default)]
+/// struct Config {
+///     message: String,
+///     retries: u32
+/// }
+///
+/// let drift = graze::drift_report(
+///     "Config.toml",
+///     |c| toml::from_str(c),
+///     Config::default,
+///     |config: &Config, default: &Config| {
+///         let mut changed = Vec::new();
+///         if config.message != default.message {
+///             changed.push("message".to_string());
+///         }
+///         if config.retries != default.retries {
+///             changed.push("retries".to_string());
+///         }
+///         changed
+///     },
+/// )
+/// .expect("Could not load configuration");
+///
+/// for field in drift {
+///     println!("{field} differs from its default");
+/// }
+/// ```
+pub fn drift_report<P, T, E, D, F, C>(
+    path: P,
+    deserializer: D,
+    default: F,
+    compare: C,
+) -> Result<Vec<String>, E>
+where
+    P: AsRef<Path>,
+    D: FnOnce(&str) -> std::result::Result<T, E>,
+    F: FnOnce() -> T,
+    C: FnOnce(&T, &T) -> Vec<String>,
+{
+    let config = load_from_path(path, deserializer)?;
+
+    Ok(compare(&config, &default()))
+}
+
+/// Append a run of removed and added lines to `diffs`, pairing them up as changed lines.
+fn flush(
+    diffs: &mut Vec<LineDiff>,
+    removed: &mut Vec<(usize, &str)>,
+    added: &mut Vec<(usize, &str)>,
+) {
+    let mut removed = removed.drain(..);
+    let mut added = added.drain(..);
+
+    loop {
+        let diff = match (removed.next(), added.next()) {
+            (Some((old_line, old)), Some((new_line, new))) => LineDiff::Changed {
+                old_line,
+                new_line,
+                old: old.to_string(),
+                new: new.to_string(),
+            },
+            (Some((old_line, content)), None) => LineDiff::Removed {
+                old_line,
+                content: content.to_string(),
+            },
+            (None, Some((new_line, content))) => LineDiff::Added {
+                new_line,
+                content: content.to_string(),
+            },
+            (None, None) => return,
+        };
+        diffs.push(diff);
+    }
+}
@@ -0,0 +1,165 @@
+//! Loading configurations split across the files of a directory.
+
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use crate::{load_from_path, read_error, ConfigurationError, Result};
+
+/// Load a configuration from each file in the given directory.
+///
+/// The files are loaded in the order of their names, so the result does not depend on the order in
+/// which the filesystem lists them. Entries which are not files (e.g. subdirectories) and hidden
+/// files, whose name starts with a `.`, are skipped. If a file cannot be deserialized,
+/// [ConfigurationError::DeserializeFile] is returned with the path of that file.
+///
+/// # Examples
+///
+/// ```no_run
+/// use serde::Deserialize;
+///
+/// #[derive(Deserialize)]
+/// struct Fragment {
+///     message: String
+/// }
+///
+/// let fragments: Vec<Fragment> = graze::load_dir("conf.d", |c| toml::from_str(c))
+///     .expect("Could not load configuration");
+/// ```
+pub fn load_dir<P, T, E, D>(dir: P, deserializer: D) -> Result<Vec<T>, E>
+where
+    P: AsRef<Path>,
+    D: Fn(&str) -> std::result::Result<T, E>,
+{
+    load_dir_matching(dir.as_ref(), deserializer, |_| true)
+}
+
+/// Load a configuration from each file in the given directory whose extension is one of the given
+/// extensions.
+///
+/// Extensions are given without the leading `.` (e.g. `"toml"`) and compared case-sensitively.
+/// Files with any other extension, or none, are skipped. See [load_dir] for details.
+///
+/// # Examples
+///
+/// ```no_run
+/// use serde::Deserialize;
+///
+/// #[derive(Deserialize)]
+/// struct Fragment {
+///     message: String
+/// }
+///
+/// let fragments: Vec<Fragment> =
+///     graze::load_dir_with_extensions("conf.d", &["toml"], |c| toml::from_str(c))
+///         .expect("Could not load configuration");
+/// ```
+pub fn load_dir_with_extensions<P, T, E, D>(
+    dir: P,
+    extensions: &[&str],
+    deserializer: D,
+) -> Result<Vec<T>, E>
+where
+    P: AsRef<Path>,
+    D: Fn(&str) -> std::result::Result<T, E>,
+{
+    load_dir_matching(dir.as_ref(), deserializer, |path| {
+        path.extension()
+            .is_some_and(|ext| extensions.iter().any(|e| ext == *e))
+    })
+}
+
+/// Load a configuration from each file in the given directory, folding them into a single
+/// configuration using the given function.
+///
+/// The accumulator starts out as its [Default] value, and `merge` is called with it and the
+/// configuration of each file in turn, in the order of the file names. This is useful when the
+/// files together make up one configuration, e.g. each file contributing entries to a map, rather
+/// than a list of separate configurations. See [load_dir] for details.
+///
+/// # Examples
+///
+/// ```no_run
+/// use std::collections::HashMap;
+///
+/// use serde::Deserialize;
+///
+/// #[derive(Deserialize)]
+/// struct Fragment {
+///     name: String,
+///     message: String
+/// }
+///
+/// let messages: HashMap<String, String> = graze::load_dir_merged(
+///     "conf.d",
+///     |c| toml::from_str(c),
+///     |messages: &mut HashMap<_, _>, fragment: Fragment| {
+///         messages.insert(fragment.name, fragment.message);
+///     },
+/// )
+/// .expect("Could not load configuration");
+/// ```
+pub fn load_dir_merged<P, T, E, D, M, A>(dir: P, deserializer: D, mut merge: M) -> Result<A, E>
+where
+    P: AsRef<Path>,
+    D: Fn(&str) -> std::result::Result<T, E>,
+    M: FnMut(&mut A, T),
+    A: Default,
+{
+    let mut acc = A::default();
+
+    for path in dir_files(dir.as_ref(), |_| true)? {
+        merge(&mut acc, load_dir_file(path, &deserializer)?);
+    }
+    Ok(acc)
+}
+
+/// Load a configuration from each file in `dir` for which `matches` returns `true`.
+///
+/// See [load_dir].
+fn load_dir_matching<T, E, D, M>(dir: &Path, deserializer: D, matches: M) -> Result<Vec<T>, E>
+where
+    D: Fn(&str) -> std::result::Result<T, E>,
+    M: Fn(&Path) -> bool,
+{
+    dir_files(dir, matches)?
+        .into_iter()
+        .map(|path| load_dir_file(path, &deserializer))
+        .collect()
+}
+
+/// List the files in `dir` for which `matches` returns `true`, sorted by name.
+///
+/// Entries which are not files and hidden files are skipped.
+fn dir_files<E, M>(dir: &Path, matches: M) -> Result<Vec<PathBuf>, E>
+where
+    M: Fn(&Path) -> bool,
+{
+    let mut paths = Vec::new();
+
+    for entry in fs::read_dir(dir).map_err(|err| read_error(dir, err))? {
+        let entry = entry.map_err(|err| ConfigurationError::io(dir, err))?;
+        let path = entry.path();
+
+        let hidden = entry.file_name().to_string_lossy().starts_with('.');
+        if !hidden && path.is_file() && matches(&path) {
+            paths.push(path);
+        }
+    }
+    paths.sort();
+
+    Ok(paths)
+}
+
+/// Load a configuration from the file at `path`, one of several files in a directory, reporting a
+/// deserializer error as [ConfigurationError::DeserializeFile].
+fn load_dir_file<T, E, D>(path: PathBuf, deserializer: D) -> Result<T, E>
+where
+    D: FnOnce(&str) -> std::result::Result<T, E>,
+{
+    match load_from_path(&path, deserializer) {
+        Err(ConfigurationError::Deserialize(source)) => {
+            Err(ConfigurationError::DeserializeFile { path, source })
+        }
+        result => result,
+    }
+}
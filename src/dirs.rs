@@ -0,0 +1,70 @@
+//! Platform configuration directories and home directory expansion, backed by [directories].
+
+use std::path::{Component, Path, PathBuf};
+
+use directories::{BaseDirs, ProjectDirs};
+
+/// Get the path of a configuration file in the platform's configuration directory for the given
+/// application.
+///
+/// The directory is resolved by [ProjectDirs::from], e.g. `$XDG_CONFIG_HOME/<app>` or
+/// `~/.config/<app>` on Linux, `~/Library/Application Support/<qualifier>.<org>.<app>` on macOS,
+/// and `%APPDATA%\<org>\<app>\config` on Windows.
+///
+/// Returns [None] if no home directory could be found.
+///
+/// # Examples
+///
+/// ```no_run
+/// use serde::{Serialize, Deserialize};
+///
+/// #[derive(Serialize, Deserialize, Default)]
+/// struct Config {
+///     message: String
+/// }
+///
+/// let path = graze::default_config_path("com", "Example", "MyApp", "Config.toml")
+///     .expect("Could not find a home directory");
+///
+/// let config = graze::load_or_write_default(
+///     path,
+///     |s| toml::from_str(s),
+///     |c| toml::to_string(c).map_err(serde::de::Error::custom),
+///     Config::default,
+/// );
+/// ```
+pub fn default_config_path(
+    qualifier: &str,
+    organization: &str,
+    application: &str,
+    filename: &str,
+) -> Option<PathBuf> {
+    let dirs = ProjectDirs::from(qualifier, organization, application)?;
+    Some(dirs.config_dir().join(filename))
+}
+
+/// Expand a leading `~` in the given path to the home directory of the current user.
+///
+/// Only a `~` forming the first component of the path is expanded, e.g. `~` or `~/Config.toml`;
+/// `~user/Config.toml` and tildes elsewhere in the path are left untouched. The path is returned
+/// unchanged if it does not start with `~`, or if no home directory could be found.
+///
+/// # Examples
+///
+/// ```no_run
+/// let path = graze::expand_tilde("~/.myapp/Config.toml");
+/// ```
+pub fn expand_tilde<P>(path: P) -> PathBuf
+where
+    P: AsRef<Path>,
+{
+    let path = path.as_ref();
+    let mut components = path.components();
+
+    match (components.next(), BaseDirs::new()) {
+        (Some(Component::Normal(first)), Some(dirs)) if first == "~" => {
+            dirs.home_dir().join(components.as_path())
+        }
+        _ => path.to_path_buf(),
+    }
+}
@@ -0,0 +1,44 @@
+//! Editing TOML configuration files while preserving their comments and formatting, backed by
+//! [toml_edit].
+
+use std::path::Path;
+
+use toml_edit::{DocumentMut, TomlError};
+
+use crate::{read_to_string, write_atomic, ConfigurationError, Result};
+
+/// Edit the TOML configuration file at the given path, preserving its comments and formatting.
+///
+/// The file is parsed into a [DocumentMut], which is passed to `edit`, and then written back
+/// atomically, in the same way as [save_to_path](crate::save_to_path). Unlike saving a
+/// configuration which was deserialized with `serde`, only the parts of the file changed by `edit`
+/// are affected; comments, whitespace and the order of keys are kept as they were. The value
+/// returned by `edit` is returned once the file was written.
+///
+/// The parse error is boxed, as [TomlError] is large enough to make every [Result] returned by
+/// this function large otherwise.
+///
+/// # Examples
+///
+/// ```no_run
+/// graze::edit_toml("Config.toml", |doc| {
+///     doc["message"] = toml_edit::value("Hello, world!");
+/// })
+/// .expect("Could not edit configuration");
+/// ```
+pub fn edit_toml<P, R, F>(path: P, edit: F) -> Result<R, Box<TomlError>>
+where
+    P: AsRef<Path>,
+    F: FnOnce(&mut DocumentMut) -> R,
+{
+    let path = path.as_ref();
+    let mut doc = read_to_string(path)?
+        .parse::<DocumentMut>()
+        .map_err(|err| ConfigurationError::deserialize(Box::new(err)))?;
+
+    let result = edit(&mut doc);
+    write_atomic(path, doc.to_string().as_bytes())
+        .map_err(|err| ConfigurationError::io(path, err))?;
+
+    Ok(result)
+}
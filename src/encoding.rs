@@ -0,0 +1,48 @@
+//! Loading configuration files in encodings other than UTF-8, backed by [encoding_rs].
+
+use std::path::Path;
+
+pub use encoding_rs::Encoding;
+
+use crate::{load_from_str, read_bytes, Result};
+
+/// Load a configuration from the file at the given path, decoding its content with the given
+/// encoding rather than UTF-8.
+///
+/// This is useful for legacy configuration files saved in e.g. Windows-1252 or Shift_JIS, which
+/// [load_from_path](crate::load_from_path) rejects. A byte order mark of the given encoding is
+/// removed, and byte sequences which are invalid in the encoding are replaced with U+FFFD rather
+/// than causing an error. Other errors are reported in the same way as
+/// [load_from_path](crate::load_from_path).
+///
+/// # Examples
+///
+/// ```no_run
+/// use serde::Deserialize;
+///
+/// #[derive(Deserialize)]
+/// struct Config {
+///     message: String
+/// }
+///
+/// let config: Config = graze::load_from_path_encoded(
+///     "Config.toml",
+///     encoding_rs::WINDOWS_1252,
+///     |c| toml::from_str(c),
+/// )
+/// .expect("Could not load configuration");
+/// ```
+pub fn load_from_path_encoded<P, T, E, D>(
+    path: P,
+    encoding: &'static Encoding,
+    deserializer: D,
+) -> Result<T, E>
+where
+    P: AsRef<Path>,
+    D: FnOnce(&str) -> std::result::Result<T, E>,
+{
+    let content = read_bytes(path.as_ref())?;
+    let (content, _) = encoding.decode_with_bom_removal(&content);
+
+    load_from_str(&content, deserializer)
+}
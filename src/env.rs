@@ -0,0 +1,172 @@
+//! Expanding environment variables referenced by configuration files, and setting environment
+//! variables from configuration files.
+
+use std::ffi::OsStr;
+use std::path::Path;
+use std::{env, io};
+
+use crate::{load_from_str, read_to_string, ConfigurationError, Result};
+
+/// How [load_from_path_expanded] treats references to environment variables which are not set.
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum Expansion {
+    /// Leave the reference as it is.
+    Lenient,
+
+    /// Fail with [ConfigurationError::UndefinedVariable].
+    Strict,
+}
+
+/// Load a configuration from the file at the given path, expanding the environment variables it
+/// references.
+///
+/// Every `${NAME}` in the content of the file is replaced with the value of the environment
+/// variable `NAME` before the content is passed to the deserializer, and `$$` is replaced with a
+/// single `$`. Any other `$` is left as it is. A variable which is not set (or whose value is not
+/// valid unicode) is handled according to `expansion`. See
+/// [load_from_path](crate::load_from_path) for details.
+///
+/// # Examples
+///
+/// ```no_run
+/// use graze::Expansion;
+/// use serde::Deserialize;
+///
+/// #[derive(Deserialize)]
+/// struct Config {
+///     data_dir: String
+/// }
+///
+/// // With `data_dir = "${HOME}/data"` in Config.toml.
+/// let config: Config =
+///     graze::load_from_path_expanded("Config.toml", |c| toml::from_str(c), Expansion::Strict)
+///         .expect("Could not load configuration");
+/// ```
+pub fn load_from_path_expanded<P, T, E, D>(
+    path: P,
+    deserializer: D,
+    expansion: Expansion,
+) -> Result<T, E>
+where
+    P: AsRef<Path>,
+    D: FnOnce(&str) -> std::result::Result<T, E>,
+{
+    let content = read_to_string(path.as_ref())?;
+    let expanded = expand(&content, expansion)?;
+
+    load_from_str(&expanded, deserializer)
+}
+
+/// Load pairs of names and values from the file at the given path, and set them as environment
+/// variables of the current process.
+///
+/// This is useful for configuring child processes, which inherit the environment. If `overwrite`
+/// is `false`, variables which are already set are left as they are. If the deserializer fails, no
+/// variables are set. See [load_from_path](crate::load_from_path) for details.
+///
+/// If a name is empty or contains `=` or a NUL character, or a value contains a NUL character,
+/// [ConfigurationError::Io] is returned with [io::ErrorKind::InvalidInput], and no variables are
+/// set.
+///
+/// As with [env::set_var], this should only be called while no other threads read or write the
+/// environment.
+///
+/// # Examples
+///
+/// ```no_run
+/// use std::collections::HashMap;
+///
+/// graze::load_into_env("Env.toml", |c| toml::from_str::<HashMap<String, String>>(c), false)
+///     .expect("Could not load environment");
+///
+/// std::process::Command::new("child").spawn().expect("Could not start child");
+/// ```
+pub fn load_into_env<P, I, K, V, E, D>(path: P, deserializer: D, overwrite: bool) -> Result<(), E>
+where
+    P: AsRef<Path>,
+    D: FnOnce(&str) -> std::result::Result<I, E>,
+    I: IntoIterator<Item = (K, V)>,
+    K: AsRef<OsStr>,
+    V: AsRef<OsStr>,
+{
+    let path = path.as_ref();
+    let content = read_to_string(path)?;
+    let pairs: Vec<_> = load_from_str(&content, deserializer)?.into_iter().collect();
+
+    let invalid = pairs
+        .iter()
+        .find(|(name, value)| !is_valid_var(name.as_ref(), value.as_ref()));
+
+    if let Some((name, _)) = invalid {
+        return Err(ConfigurationError::io(
+            path,
+            io::Error::new(
+                io::ErrorKind::InvalidInput,
+                format!(
+                    "'{}' cannot be set as an environment variable",
+                    name.as_ref().to_string_lossy()
+                ),
+            ),
+        ));
+    }
+
+    for (name, value) in pairs {
+        if overwrite || env::var_os(&name).is_none() {
+            env::set_var(name, value);
+        }
+    }
+
+    Ok(())
+}
+
+/// Check whether a variable with the given name and value can be set by [env::set_var] without
+/// panicking.
+fn is_valid_var(name: &OsStr, value: &OsStr) -> bool {
+    let name = name.as_encoded_bytes();
+
+    !name.is_empty()
+        && !name.contains(&b'=')
+        && !name.contains(&0)
+        && !value.as_encoded_bytes().contains(&0)
+}
+
+/// Expand the environment variables referenced by `content`.
+fn expand<E>(content: &str, expansion: Expansion) -> Result<String, E> {
+    let mut expanded = String::with_capacity(content.len());
+    let mut rest = content;
+
+    while let Some(i) = rest.find('$') {
+        expanded.push_str(&rest[..i]);
+        rest = &rest[i..];
+
+        if let Some(after) = rest.strip_prefix("$$") {
+            expanded.push('$');
+            rest = after;
+            continue;
+        }
+
+        let reference = rest
+            .strip_prefix("${")
+            .and_then(|after| after.find('}').map(|end| &after[..end]));
+
+        let Some(name) = reference else {
+            expanded.push('$');
+            rest = &rest[1..];
+            continue;
+        };
+
+        match (env::var(name), expansion) {
+            (Ok(value), _) => expanded.push_str(&value),
+            (Err(_), Expansion::Lenient) => expanded.push_str(&rest[..name.len() + 3]),
+            (Err(_), Expansion::Strict) => {
+                return Err(ConfigurationError::UndefinedVariable {
+                    name: name.to_string(),
+                })
+            }
+        }
+        rest = &rest[name.len() + 3..];
+    }
+    expanded.push_str(rest);
+
+    Ok(expanded)
+}
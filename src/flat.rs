@@ -0,0 +1,198 @@
+//! A minimal built-in format of flat `key = value` pairs, for simple configurations which do not
+//! need a `serde` format.
+
+use std::collections::HashMap;
+use std::path::Path;
+
+use thiserror::Error;
+
+use crate::{load_from_path, Result};
+
+/// An error from parsing a flat configuration, as returned by [parse_flat].
+#[derive(Clone, Debug, Eq, Error, PartialEq)]
+pub enum ParseError {
+    /// A line is neither blank, a comment nor a `key = value` pair.
+    #[error("Line {line} is not a `key = value` pair")]
+    MissingSeparator {
+        /// The line number, starting at 1.
+        line: usize,
+    },
+
+    /// A line has a `=` but no key before it.
+    #[error("Line {line} has no key before the `=`")]
+    EmptyKey {
+        /// The line number, starting at 1.
+        line: usize,
+    },
+}
+
+/// Parse a flat configuration of `key = value` pairs, one per line.
+///
+/// The key and value are separated by the first `=` of the line, and surrounding whitespace is
+/// trimmed from both. A value enclosed in matching double or single quotes has the quotes removed,
+/// so that it may keep leading or trailing whitespace. Blank lines and lines starting with `#` are
+/// ignored; a `#` anywhere else is part of the value. If a key occurs more than once, its last
+/// value is used.
+///
+/// # Examples
+///
+/// ```
+/// let config = graze::parse_flat("# The greeting\nmessage = \"Hello, world!\"\n").unwrap();
+///
+/// assert_eq!(config["message"], "Hello, world!");
+/// ```
+pub fn parse_flat(content: &str) -> std::result::Result<HashMap<String, String>, ParseError> {
+    parse_pairs(content, Syntax::Flat).map(|pairs| pairs.into_iter().collect())
+}
+
+/// The syntax of the lines parsed by [parse_pairs].
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+enum Syntax {
+    /// The flat configuration format, as described by [parse_flat].
+    Flat,
+
+    /// The `.env` format, as described by [layer_env_file].
+    Env,
+}
+
+/// Parse the `key = value` pairs of a flat configuration or `.env` file, in the order they occur.
+///
+/// See [parse_flat] and [layer_env_file].
+fn parse_pairs(
+    content: &str,
+    syntax: Syntax,
+) -> std::result::Result<Vec<(String, String)>, ParseError> {
+    let mut pairs = Vec::new();
+
+    for (i, line) in content.lines().enumerate() {
+        let mut line = line.trim();
+
+        if line.is_empty() || line.starts_with('#') {
+            continue;
+        }
+
+        if syntax == Syntax::Env {
+            line = line.strip_prefix("export ").unwrap_or(line).trim_start();
+        }
+
+        let (key, value) = line
+            .split_once('=')
+            .ok_or(ParseError::MissingSeparator { line: i + 1 })?;
+        let key = key.trim();
+
+        if key.is_empty() {
+            return Err(ParseError::EmptyKey { line: i + 1 });
+        }
+
+        let value = match syntax {
+            Syntax::Flat => unquote(value.trim()),
+            Syntax::Env => env_value(value.trim()),
+        };
+        pairs.push((key.to_string(), value.to_string()));
+    }
+
+    Ok(pairs)
+}
+
+/// Extract the value of a `.env` line from the text following its `=`.
+///
+/// A value starting with a double or single quote ends at the matching quote, and anything after
+/// it is ignored. In an unquoted value, a `#` at the start or following whitespace begins a
+/// comment.
+fn env_value(value: &str) -> &str {
+    for quote in ['"', '\''] {
+        if let Some(quoted) = value
+            .strip_prefix(quote)
+            .and_then(|rest| rest.split_once(quote))
+        {
+            return quoted.0;
+        }
+    }
+
+    let comment = value
+        .char_indices()
+        .find(|&(i, c)| {
+            c == '#'
+                && value[..i]
+                    .chars()
+                    .next_back()
+                    .is_none_or(char::is_whitespace)
+        })
+        .map_or(value.len(), |(i, _)| i);
+
+    value[..comment].trim_end()
+}
+
+/// Remove matching double or single quotes surrounding `value`.
+fn unquote(value: &str) -> &str {
+    ['"', '\'']
+        .into_iter()
+        .find_map(|quote| value.strip_prefix(quote)?.strip_suffix(quote))
+        .unwrap_or(value)
+}
+
+/// Load a flat configuration of `key = value` pairs from the file at the given path.
+///
+/// See [parse_flat] for the format, and [load_from_path](crate::load_from_path) for details.
+///
+/// # Examples
+///
+/// ```no_run
+/// let config = graze::load_flat("Config.env").expect("Could not load configuration");
+///
+/// println!("{}", config["message"]);
+/// ```
+pub fn load_flat<P>(path: P) -> Result<HashMap<String, String>, ParseError>
+where
+    P: AsRef<Path>,
+{
+    load_from_path(path, parse_flat)
+}
+
+/// Override fields of a configuration with the `KEY=VALUE` pairs of the `.env` style file at the
+/// given path.
+///
+/// The file is parsed like a flat configuration (see [parse_flat]), with the conventions of `.env`
+/// files: a line may start with `export `, which is ignored, and a value enclosed in quotes ends
+/// at the closing quote. In an unquoted value, a `#` at the start or following whitespace begins a
+/// comment, so `HOST=example.com # local` sets `HOST` to `example.com`, while `URL=http://a/#b`
+/// keeps its `#`. `apply` is then called with the configuration and each key and value, in the
+/// order they occur in the file. Mapping keys to fields is left to `apply`, which may ignore keys
+/// it does not recognize. If the file cannot be loaded, the configuration is left untouched. See
+/// [load_from_path](crate::load_from_path) for details.
+///
+/// This is part of the `flat` feature because the `.env` syntax is parsed by the flat format's
+/// parser, and reported through its [ParseError].
+///
+/// # Examples
+///
+/// ```no_run
+/// use serde::Deserialize;
+///
+/// #[derive(Deserialize)]
+/// struct Config {
+///     host: String,
+///     token: String
+/// }
+///
+/// let mut config: Config = graze::load_from_path("Config.toml", |c| toml::from_str(c))
+///     .expect("Could not load configuration");
+///
+/// graze::layer_env_file(&mut config, ".env", |config, key, value| match key {
+///     "HOST" => config.host = value.to_string(),
+///     "TOKEN" => config.token = value.to_string(),
+///     _ => {}
+/// })
+/// .expect("Could not load .env file");
+/// ```
+pub fn layer_env_file<P, T, A>(config: &mut T, env_path: P, mut apply: A) -> Result<(), ParseError>
+where
+    P: AsRef<Path>,
+    A: FnMut(&mut T, &str, &str),
+{
+    for (key, value) in load_from_path(env_path, |c| parse_pairs(c, Syntax::Env))? {
+        apply(config, &key, &value);
+    }
+
+    Ok(())
+}
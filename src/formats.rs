@@ -0,0 +1,97 @@
+//! Convenience functions for the most common [serde] formats.
+//!
+//! These functions supply the deserializer and serializer for you, so that configurations in these
+//! formats can be loaded without any closures.
+
+#[cfg(feature = "multi-format")]
+pub use self::auto::*;
+#[cfg(feature = "bincode")]
+pub use self::bincode::*;
+#[cfg(feature = "json")]
+pub use self::json::*;
+#[cfg(feature = "toml")]
+pub use self::toml::*;
+#[cfg(feature = "yaml")]
+pub use self::yaml::*;
+
+#[cfg(feature = "multi-format")]
+mod auto;
+#[cfg(feature = "bincode")]
+mod bincode;
+#[cfg(feature = "json")]
+mod json;
+#[cfg(feature = "toml")]
+mod toml;
+#[cfg(feature = "yaml")]
+mod yaml;
+
+use std::path::Path;
+
+use serde::de::DeserializeOwned;
+use serde::Serialize;
+
+use crate::{load_from_path, save_to_path, try_load_or_write_with, write_new_atomic, Result};
+
+/// A configuration file format, which can be passed as a value instead of a pair of closures.
+///
+/// The `json`, `toml` and `yaml` features provide the [Json], [Toml] and [Yaml] implementations
+/// respectively.
+pub trait Format {
+    /// The error returned when a configuration cannot be deserialized or serialized.
+    type Error;
+
+    /// Deserialize a configuration from the given content.
+    fn deserialize<T>(&self, content: &str) -> std::result::Result<T, Self::Error>
+    where
+        T: DeserializeOwned;
+
+    /// Serialize the given configuration.
+    fn serialize<T>(&self, value: &T) -> std::result::Result<Vec<u8>, Self::Error>
+    where
+        T: Serialize;
+}
+
+/// Load a configuration in the given format from the file at the given path.
+///
+/// See [load_from_path] for details.
+pub fn load_from_path_with<P, T, F>(path: P, format: F) -> Result<T, F::Error>
+where
+    P: AsRef<Path>,
+    T: DeserializeOwned,
+    F: Format,
+{
+    load_from_path(path, |s| format.deserialize(s))
+}
+
+/// Load a configuration in the given format from the file at the given path, or use the default
+/// value if the file does not exist, writing the default value to the file.
+///
+/// An error serializing the default value is returned rather than causing a panic. See
+/// [load_or_write_default](crate::load_or_write_default) for details.
+pub fn load_or_write_default_with<P, T, F>(path: P, format: F) -> Result<T, F::Error>
+where
+    P: AsRef<Path>,
+    T: Serialize + DeserializeOwned + Default,
+    F: Format,
+{
+    try_load_or_write_with(
+        path.as_ref(),
+        |s| format.deserialize(s),
+        |c| format.serialize(c),
+        T::default,
+        write_new_atomic,
+    )
+    .map(|(data, _)| data)
+}
+
+/// Save a configuration in the given format to the file at the given path.
+///
+/// See [save_to_path] for details.
+pub fn save_to_path_with<P, T, F>(path: P, value: &T, format: F) -> Result<(), F::Error>
+where
+    P: AsRef<Path>,
+    T: Serialize,
+    F: Format,
+{
+    save_to_path(path, value, |c| format.serialize(c))
+}
@@ -0,0 +1,70 @@
+//! Loading configurations in a format chosen by their file extension.
+
+use std::path::Path;
+
+use serde::de::DeserializeOwned;
+use thiserror::Error;
+
+use crate::{load_from_path, ConfigurationError, Result};
+
+/// An error returned by [load_auto].
+#[derive(Debug, Error)]
+pub enum AutoError {
+    /// The TOML configuration could not be deserialized.
+    #[error(transparent)]
+    Toml(#[from] ::toml::de::Error),
+
+    /// The JSON configuration could not be deserialized.
+    #[error(transparent)]
+    Json(#[from] serde_json::Error),
+
+    /// The YAML configuration could not be deserialized.
+    #[error(transparent)]
+    Yaml(#[from] serde_yaml::Error),
+
+    /// The file extension does not belong to a supported format.
+    #[error("Unsupported configuration file extension '{0}'")]
+    UnsupportedExtension(String),
+}
+
+/// Load a configuration from the file at the given path, choosing the format by the file
+/// extension.
+///
+/// Files ending in `.toml` are loaded as TOML, `.json` as JSON, and `.yaml` or `.yml` as YAML. Any
+/// other extension results in [AutoError::UnsupportedExtension], without the file being read.
+///
+/// # Examples
+///
+/// ```no_run
+/// use serde::Deserialize;
+///
+/// #[derive(Deserialize)]
+/// struct Config {
+///     message: String
+/// }
+///
+/// // Config.json or Config.yaml could be used interchangeably.
+/// let config: Config = graze::load_auto("Config.toml").expect("Could not load configuration");
+/// ```
+pub fn load_auto<P, T>(path: P) -> Result<T, AutoError>
+where
+    P: AsRef<Path>,
+    T: DeserializeOwned,
+{
+    let path = path.as_ref();
+    let extension = path
+        .extension()
+        .map(|e| e.to_string_lossy().into_owned())
+        .unwrap_or_default();
+
+    match extension.as_str() {
+        "toml" => load_from_path(path, |s| ::toml::from_str(s).map_err(AutoError::from)),
+        "json" => load_from_path(path, |s| serde_json::from_str(s).map_err(AutoError::from)),
+        "yaml" | "yml" => {
+            load_from_path(path, |s| serde_yaml::from_str(s).map_err(AutoError::from))
+        }
+        _ => Err(ConfigurationError::Deserialize(
+            AutoError::UnsupportedExtension(extension),
+        )),
+    }
+}
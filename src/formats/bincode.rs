@@ -0,0 +1,65 @@
+//! Convenience functions for [bincode] files.
+
+use std::path::Path;
+
+use serde::de::DeserializeOwned;
+use serde::Serialize;
+
+use crate::{
+    load_from_path_bytes, save_to_path, try_load_or_write_bytes_with, write_new_atomic, Result,
+};
+
+/// Load a bincode configuration from the file at the given path.
+///
+/// See [load_from_path_bytes] for details.
+///
+/// # Examples
+///
+/// ```no_run
+/// use serde::Deserialize;
+///
+/// #[derive(Deserialize)]
+/// struct Config {
+///     message: String
+/// }
+///
+/// let config: Config = graze::load_bincode("Config.bin").expect("Could not load configuration");
+/// ```
+pub fn load_bincode<P, T>(path: P) -> Result<T, bincode::Error>
+where
+    P: AsRef<Path>,
+    T: DeserializeOwned,
+{
+    load_from_path_bytes(path, |b| bincode::deserialize(b))
+}
+
+/// Load a bincode configuration from the file at the given path, or use the default value if the
+/// file does not exist, writing the default value to the file.
+///
+/// An error serializing the default value is returned rather than causing a panic. See
+/// [load_or_write_default_bytes](crate::load_or_write_default_bytes) for details.
+pub fn load_or_write_default_bincode<P, T>(path: P) -> Result<T, bincode::Error>
+where
+    P: AsRef<Path>,
+    T: Serialize + DeserializeOwned + Default,
+{
+    try_load_or_write_bytes_with(
+        path.as_ref(),
+        |b| bincode::deserialize(b),
+        bincode::serialize,
+        T::default,
+        write_new_atomic,
+    )
+    .map(|(data, _)| data)
+}
+
+/// Save a bincode configuration to the file at the given path.
+///
+/// See [save_to_path] for details.
+pub fn save_bincode<P, T>(path: P, value: &T) -> Result<(), bincode::Error>
+where
+    P: AsRef<Path>,
+    T: Serialize,
+{
+    save_to_path(path, value, bincode::serialize)
+}
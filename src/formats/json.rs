@@ -0,0 +1,178 @@
+//! Convenience functions for [JSON](serde_json) files.
+
+use std::path::Path;
+
+use serde::de::DeserializeOwned;
+use serde::Serialize;
+
+use crate::{
+    load_from_path, load_or_default, save_to_path, try_load_or_write_with, write_new_atomic,
+    Format, Result,
+};
+
+/// The [JSON](serde_json) [Format].
+#[derive(Clone, Copy, Debug, Default, Eq, PartialEq)]
+pub struct Json;
+
+impl Format for Json {
+    type Error = serde_json::Error;
+
+    fn deserialize<T>(&self, content: &str) -> std::result::Result<T, Self::Error>
+    where
+        T: DeserializeOwned,
+    {
+        serde_json::from_str(content)
+    }
+
+    fn serialize<T>(&self, value: &T) -> std::result::Result<Vec<u8>, Self::Error>
+    where
+        T: Serialize,
+    {
+        serde_json::to_vec(value)
+    }
+}
+
+/// Load a JSON configuration from the file at the given path.
+///
+/// See [load_from_path] for details.
+///
+/// # Examples
+///
+/// ```no_run
+/// use serde::Deserialize;
+///
+/// #[derive(Deserialize)]
+/// struct Config {
+///     message: String
+/// }
+///
+/// let config: Config = graze::load_json("Config.json").expect("Could not load configuration");
+/// ```
+pub fn load_json<P, T>(path: P) -> Result<T, serde_json::Error>
+where
+    P: AsRef<Path>,
+    T: DeserializeOwned,
+{
+    load_from_path(path, |s| serde_json::from_str(s))
+}
+
+/// Load a JSON configuration from the file at the given path, failing if the file contains fields
+/// which the configuration does not have.
+///
+/// This catches typos in field names, which [load_json] silently ignores. It has the same effect as
+/// adding `#[serde(deny_unknown_fields)]` to every type in the configuration, but without changing
+/// the types, so the same configuration can still be loaded leniently elsewhere. The first unknown
+/// field is reported as [ConfigurationError::Deserialize](crate::ConfigurationError::Deserialize),
+/// along with its path (e.g. `server.prot`). See [load_from_path] for details.
+///
+/// # Examples
+///
+/// ```no_run
+/// use serde::Deserialize;
+///
+/// #[derive(Deserialize)]
+/// struct Config {
+///     message: String
+/// }
+///
+/// let config: Config =
+///     graze::load_json_strict("Config.json").expect("Could not load configuration");
+/// ```
+pub fn load_json_strict<P, T>(path: P) -> Result<T, serde_json::Error>
+where
+    P: AsRef<Path>,
+    T: DeserializeOwned,
+{
+    load_from_path(path, from_str_strict)
+}
+
+/// Load a JSON configuration from the file at the given path, or use the default value if the file
+/// does not exist.
+///
+/// See [load_or_default] for details.
+pub fn load_or_default_json<P, T>(path: P) -> Result<T, serde_json::Error>
+where
+    P: AsRef<Path>,
+    T: DeserializeOwned + Default,
+{
+    load_or_default(path, |s| serde_json::from_str(s), T::default)
+}
+
+/// Load a JSON configuration from the file at the given path, or use the default value if the file
+/// does not exist, writing the default value to the file.
+///
+/// If `pretty` is `true`, the default value is written indented across several lines for editing
+/// by hand; otherwise it is written on a single line. An error serializing the default value is
+/// returned rather than causing a panic. See [load_or_write_default](crate::load_or_write_default)
+/// for details.
+///
+/// # Examples
+///
+/// ```no_run
+/// use serde::{Serialize, Deserialize};
+///
+/// #[derive(Serialize, Deserialize, Default)]
+/// struct Config {
+///     message: String
+/// }
+///
+/// let config: Config = graze::load_or_write_default_json("Config.json", true)
+///     .expect("Could not load configuration");
+/// ```
+pub fn load_or_write_default_json<P, T>(path: P, pretty: bool) -> Result<T, serde_json::Error>
+where
+    P: AsRef<Path>,
+    T: Serialize + DeserializeOwned + Default,
+{
+    try_load_or_write_with(
+        path.as_ref(),
+        |s| serde_json::from_str(s),
+        |c| to_string(c, pretty),
+        T::default,
+        write_new_atomic,
+    )
+    .map(|(data, _)| data)
+}
+
+/// Save a JSON configuration to the file at the given path.
+///
+/// If `pretty` is `true`, the configuration is written indented across several lines; otherwise it
+/// is written on a single line. See [save_to_path] for details.
+pub fn save_json<P, T>(path: P, value: &T, pretty: bool) -> Result<(), serde_json::Error>
+where
+    P: AsRef<Path>,
+    T: Serialize,
+{
+    save_to_path(path, value, |c| to_string(c, pretty))
+}
+
+/// Serialize `value` to JSON, indented across several lines if `pretty` is `true`.
+fn to_string<T>(value: &T, pretty: bool) -> serde_json::Result<String>
+where
+    T: Serialize + ?Sized,
+{
+    if pretty {
+        serde_json::to_string_pretty(value)
+    } else {
+        serde_json::to_string(value)
+    }
+}
+
+/// Deserialize `content` as JSON, failing on the first field which `T` does not have.
+fn from_str_strict<T>(content: &str) -> serde_json::Result<T>
+where
+    T: DeserializeOwned,
+{
+    let mut unknown = None;
+    let mut deserializer = serde_json::Deserializer::from_str(content);
+
+    let value = serde_ignored::deserialize(&mut deserializer, |path| {
+        unknown.get_or_insert_with(|| path.to_string());
+    })?;
+    deserializer.end()?;
+
+    match unknown {
+        Some(path) => Err(serde::de::Error::custom(format!("unknown field `{path}`"))),
+        None => Ok(value),
+    }
+}
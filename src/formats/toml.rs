@@ -0,0 +1,147 @@
+//! Convenience functions for [TOML](::toml) files.
+
+use std::path::Path;
+
+use serde::de::DeserializeOwned;
+use serde::Serialize;
+use thiserror::Error;
+
+use crate::{
+    load_from_path, load_or_default, save_to_path, try_load_or_write_with, write_new_atomic,
+    Format, Result,
+};
+
+/// An error returned by [load_or_write_default_toml] and [Toml], which both deserialize and
+/// serialize TOML.
+#[derive(Debug, Error)]
+pub enum TomlError {
+    /// The configuration could not be deserialized.
+    #[error(transparent)]
+    Deserialize(#[from] ::toml::de::Error),
+
+    /// The configuration could not be serialized.
+    #[error(transparent)]
+    Serialize(#[from] ::toml::ser::Error),
+}
+
+/// The [TOML](::toml) [Format].
+///
+/// # Examples
+///
+/// ```no_run
+/// use graze::Toml;
+/// use serde::{Serialize, Deserialize};
+///
+/// #[derive(Serialize, Deserialize)]
+/// struct Config {
+///     message: String
+/// }
+///
+/// let mut config: Config =
+///     graze::load_from_path_with("Config.toml", Toml).expect("Could not load configuration");
+///
+/// config.message = "Hello, world!".to_string();
+///
+/// graze::save_to_path_with("Config.toml", &config, Toml).expect("Could not save configuration");
+/// ```
+#[derive(Clone, Copy, Debug, Default, Eq, PartialEq)]
+pub struct Toml;
+
+impl Format for Toml {
+    type Error = TomlError;
+
+    fn deserialize<T>(&self, content: &str) -> std::result::Result<T, Self::Error>
+    where
+        T: DeserializeOwned,
+    {
+        Ok(::toml::from_str(content)?)
+    }
+
+    fn serialize<T>(&self, value: &T) -> std::result::Result<Vec<u8>, Self::Error>
+    where
+        T: Serialize,
+    {
+        Ok(::toml::to_vec(value)?)
+    }
+}
+
+/// Load a TOML configuration from the file at the given path.
+///
+/// See [load_from_path] for details.
+///
+/// # Examples
+///
+/// ```no_run
+/// use serde::Deserialize;
+///
+/// #[derive(Deserialize)]
+/// struct Config {
+///     message: String
+/// }
+///
+/// let config: Config = graze::load_toml("Config.toml").expect("Could not load configuration");
+/// ```
+pub fn load_toml<P, T>(path: P) -> Result<T, ::toml::de::Error>
+where
+    P: AsRef<Path>,
+    T: DeserializeOwned,
+{
+    load_from_path(path, |s| ::toml::from_str(s))
+}
+
+/// Load a TOML configuration from the file at the given path, or use the default value if the file
+/// does not exist.
+///
+/// See [load_or_default] for details.
+pub fn load_or_default_toml<P, T>(path: P) -> Result<T, ::toml::de::Error>
+where
+    P: AsRef<Path>,
+    T: DeserializeOwned + Default,
+{
+    load_or_default(path, |s| ::toml::from_str(s), T::default)
+}
+
+/// Load a TOML configuration from the file at the given path, or use the default value if the file
+/// does not exist, writing the default value to the file.
+///
+/// An error serializing the default value is returned rather than causing a panic. See
+/// [load_or_write_default](crate::load_or_write_default) for details.
+///
+/// # Examples
+///
+/// ```no_run
+/// use serde::{Serialize, Deserialize};
+///
+/// #[derive(Serialize, Deserialize, Default)]
+/// struct Config {
+///     message: String
+/// }
+///
+/// let config: Config =
+///     graze::load_or_write_default_toml("Config.toml").expect("Could not load configuration");
+/// ```
+pub fn load_or_write_default_toml<P, T>(path: P) -> Result<T, TomlError>
+where
+    P: AsRef<Path>,
+    T: Serialize + DeserializeOwned + Default,
+{
+    try_load_or_write_with(
+        path.as_ref(),
+        |s| ::toml::from_str(s).map_err(TomlError::from),
+        |c| ::toml::to_string(c).map_err(TomlError::from),
+        T::default,
+        write_new_atomic,
+    )
+    .map(|(data, _)| data)
+}
+
+/// Save a TOML configuration to the file at the given path.
+///
+/// See [save_to_path] for details.
+pub fn save_toml<P, T>(path: P, value: &T) -> Result<(), ::toml::ser::Error>
+where
+    P: AsRef<Path>,
+    T: Serialize,
+{
+    save_to_path(path, value, ::toml::to_string)
+}
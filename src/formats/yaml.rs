@@ -0,0 +1,114 @@
+//! Convenience functions for [YAML](serde_yaml) files.
+
+use std::path::Path;
+
+use serde::de::DeserializeOwned;
+use serde::Serialize;
+
+use crate::{
+    load_from_path, load_or_default, save_to_path, try_load_or_write_with, write_new_atomic,
+    Format, Result,
+};
+
+/// The [YAML](serde_yaml) [Format].
+#[derive(Clone, Copy, Debug, Default, Eq, PartialEq)]
+pub struct Yaml;
+
+impl Format for Yaml {
+    type Error = serde_yaml::Error;
+
+    fn deserialize<T>(&self, content: &str) -> std::result::Result<T, Self::Error>
+    where
+        T: DeserializeOwned,
+    {
+        serde_yaml::from_str(content)
+    }
+
+    fn serialize<T>(&self, value: &T) -> std::result::Result<Vec<u8>, Self::Error>
+    where
+        T: Serialize,
+    {
+        serde_yaml::to_string(value).map(String::into_bytes)
+    }
+}
+
+/// Load a YAML configuration from the file at the given path.
+///
+/// See [load_from_path] for details.
+///
+/// # Examples
+///
+/// ```no_run
+/// use serde::Deserialize;
+///
+/// #[derive(Deserialize)]
+/// struct Config {
+///     message: String
+/// }
+///
+/// let config: Config = graze::load_yaml("Config.yaml").expect("Could not load configuration");
+/// ```
+pub fn load_yaml<P, T>(path: P) -> Result<T, serde_yaml::Error>
+where
+    P: AsRef<Path>,
+    T: DeserializeOwned,
+{
+    load_from_path(path, |s| serde_yaml::from_str(s))
+}
+
+/// Load a YAML configuration from the file at the given path, or use the default value if the file
+/// does not exist.
+///
+/// See [load_or_default] for details.
+pub fn load_or_default_yaml<P, T>(path: P) -> Result<T, serde_yaml::Error>
+where
+    P: AsRef<Path>,
+    T: DeserializeOwned + Default,
+{
+    load_or_default(path, |s| serde_yaml::from_str(s), T::default)
+}
+
+/// Load a YAML configuration from the file at the given path, or use the default value if the file
+/// does not exist, writing the default value to the file.
+///
+/// An error serializing the default value is returned rather than causing a panic. See
+/// [load_or_write_default](crate::load_or_write_default) for details.
+///
+/// # Examples
+///
+/// ```no_run
+/// use serde::{Serialize, Deserialize};
+///
+/// #[derive(Serialize, Deserialize, Default)]
+/// struct Config {
+///     message: String
+/// }
+///
+/// let config: Config =
+///     graze::load_or_write_default_yaml("Config.yaml").expect("Could not load configuration");
+/// ```
+pub fn load_or_write_default_yaml<P, T>(path: P) -> Result<T, serde_yaml::Error>
+where
+    P: AsRef<Path>,
+    T: Serialize + DeserializeOwned + Default,
+{
+    try_load_or_write_with(
+        path.as_ref(),
+        |s| serde_yaml::from_str(s),
+        serde_yaml::to_string,
+        T::default,
+        write_new_atomic,
+    )
+    .map(|(data, _)| data)
+}
+
+/// Save a YAML configuration to the file at the given path.
+///
+/// See [save_to_path] for details.
+pub fn save_yaml<P, T>(path: P, value: &T) -> Result<(), serde_yaml::Error>
+where
+    P: AsRef<Path>,
+    T: Serialize,
+{
+    save_to_path(path, value, serde_yaml::to_string)
+}
@@ -0,0 +1,34 @@
+//! Transparent decompression and compression of gzip configuration files, backed by [flate2].
+
+use std::borrow::Cow;
+use std::io::{self, Read, Write};
+use std::path::Path;
+
+use flate2::read::GzDecoder;
+use flate2::write::GzEncoder;
+use flate2::Compression;
+
+/// The bytes every gzip stream starts with.
+const MAGIC: [u8; 2] = [0x1f, 0x8b];
+
+/// Decompress `content` if it is a gzip stream, or return it as is otherwise.
+pub(crate) fn decompress(content: Vec<u8>) -> io::Result<Vec<u8>> {
+    if !content.starts_with(&MAGIC) {
+        return Ok(content);
+    }
+
+    let mut decompressed = Vec::new();
+    GzDecoder::new(content.as_slice()).read_to_end(&mut decompressed)?;
+    Ok(decompressed)
+}
+
+/// Compress `content` if `path` has a `.gz` extension, or return it as is otherwise.
+pub(crate) fn compress_for<'a>(path: &Path, content: &'a [u8]) -> io::Result<Cow<'a, [u8]>> {
+    if path.extension().is_none_or(|ext| ext != "gz") {
+        return Ok(Cow::Borrowed(content));
+    }
+
+    let mut encoder = GzEncoder::new(Vec::new(), Compression::default());
+    encoder.write_all(content)?;
+    encoder.finish().map(Cow::Owned)
+}
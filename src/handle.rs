@@ -0,0 +1,105 @@
+//! A handle to a loaded configuration which can be saved back to its file.
+
+use std::path::{Path, PathBuf};
+
+use crate::{save_to_path, try_load_or_write_with, write_new_atomic, Result};
+
+type Serializer<T, E> = Box<dyn Fn(&T) -> std::result::Result<Vec<u8>, E>>;
+
+/// A loaded configuration which remembers the path it was loaded from and how to serialize it.
+///
+/// This allows a configuration to be changed and saved throughout the run of a program, without
+/// passing the path and serializer around. Created by [load_or_insert_default].
+pub struct Config<T, E> {
+    value: T,
+    path: PathBuf,
+    serializer: Serializer<T, E>,
+}
+
+impl<T, E> Config<T, E> {
+    /// Get the configuration.
+    pub fn get(&self) -> &T {
+        &self.value
+    }
+
+    /// Get the configuration mutably.
+    ///
+    /// Changes are not written to the file until [Config::save] is called.
+    pub fn get_mut(&mut self) -> &mut T {
+        &mut self.value
+    }
+
+    /// Get the path of the configuration file.
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+
+    /// Get the configuration, discarding the path and serializer.
+    pub fn into_inner(self) -> T {
+        self.value
+    }
+
+    /// Save the configuration to its file.
+    ///
+    /// See [save_to_path] for details.
+    pub fn save(&self) -> Result<(), E> {
+        save_to_path(&self.path, &self.value, &self.serializer)
+    }
+}
+
+/// Load a configuration from the file at the given path, or write and use the default value if the
+/// file does not exist, returning a [Config] handle which can later save changes to the file.
+///
+/// An error returned by the serializer is propagated through
+/// [ConfigurationError::Serialize](crate::ConfigurationError::Serialize). See
+/// [load_or_write_default](crate::load_or_write_default) for details.
+///
+/// # Examples
+///
+/// ```no_run
+/// use serde::{Serialize, Deserialize};
+///
+/// #[derive(Serialize, Deserialize, Default)]
+/// struct Settings {
+///     volume: u8
+/// }
+///
+/// // The deserializer and serializer must share an error type.
+/// let mut config = graze::load_or_insert_default(
+///     "Settings.toml",
+///     |s| toml::from_str(s).map_err(|e| e.to_string()),
+///     |c| toml::to_string(c).map_err(|e| e.to_string()),
+///     Settings::default,
+/// )
+/// .expect("Could not load settings");
+///
+/// config.get_mut().volume = 11;
+/// config.save().expect("Could not save settings");
+/// ```
+pub fn load_or_insert_default<P, T, E, D, S, F, B>(
+    path: P,
+    deserializer: D,
+    serializer: S,
+    default: F,
+) -> Result<Config<T, E>, E>
+where
+    P: AsRef<Path>,
+    D: FnOnce(&str) -> std::result::Result<T, E>,
+    S: Fn(&T) -> std::result::Result<B, E> + 'static,
+    B: AsRef<[u8]>,
+    F: FnOnce() -> T,
+{
+    let (value, meta) = try_load_or_write_with(
+        path.as_ref(),
+        deserializer,
+        &serializer,
+        default,
+        write_new_atomic,
+    )?;
+
+    Ok(Config {
+        value,
+        path: meta.path,
+        serializer: Box::new(move |c| serializer(c).map(|b| b.as_ref().to_vec())),
+    })
+}
@@ -0,0 +1,67 @@
+//! Detecting changes to configuration files through hashes of their content.
+
+use std::collections::hash_map::DefaultHasher;
+use std::fs;
+use std::hash::{Hash, Hasher};
+use std::io;
+use std::path::Path;
+
+use crate::{load_from_str, read_to_string, Result};
+
+/// Load a configuration from the file at the given path, along with a hash of the file's content.
+///
+/// The hash can later be passed to [file_matches_hash] to check whether the file was changed since
+/// it was loaded, without deserializing it again. Hashes are only comparable within the same build
+/// of a program, and should not be persisted.
+///
+/// # Examples
+///
+/// ```no_run
+/// use serde::Deserialize;
+///
+/// #[derive(Deserialize)]
+/// struct Config {
+///     message: String
+/// }
+///
+/// let (config, hash): (Config, _) =
+///     graze::load_from_path_hashed("Config.toml", |c| toml::from_str(c))
+///         .expect("Could not load configuration");
+///
+/// // ...
+///
+/// if !graze::file_matches_hash("Config.toml", hash).unwrap_or(false) {
+///     println!("The configuration file was changed");
+/// }
+/// ```
+pub fn load_from_path_hashed<P, T, E, D>(path: P, deserializer: D) -> Result<(T, u64), E>
+where
+    P: AsRef<Path>,
+    D: FnOnce(&str) -> std::result::Result<T, E>,
+{
+    let content = read_to_string(path.as_ref())?;
+    let config = load_from_str(&content, deserializer)?;
+
+    Ok((config, hash_content(content.as_bytes())))
+}
+
+/// Check whether the content of the file at the given path matches a hash returned by
+/// [load_from_path_hashed].
+pub fn file_matches_hash<P>(path: P, hash: u64) -> io::Result<bool>
+where
+    P: AsRef<Path>,
+{
+    let content = fs::read(path)?;
+
+    #[cfg(feature = "gzip")]
+    let content = crate::gzip::decompress(content)?;
+
+    Ok(hash_content(&content) == hash)
+}
+
+/// Hash the content of a configuration file.
+fn hash_content(content: &[u8]) -> u64 {
+    let mut hasher = DefaultHasher::new();
+    content.hash(&mut hasher);
+    hasher.finish()
+}
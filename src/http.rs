@@ -0,0 +1,173 @@
+//! Loading configurations from HTTP(S) URLs, backed by [ureq].
+
+use std::io;
+
+use crate::{load_from_str, ConfigurationError, Result};
+
+type Deserializer<T, E> = Box<dyn Fn(&str) -> std::result::Result<T, E>>;
+
+/// The status of a response to a conditional request whose condition did not match.
+const NOT_MODIFIED: u16 = 304;
+
+/// Load a configuration from the given HTTP(S) URL.
+///
+/// The URL is fetched with a `GET` request, following redirects, and the body of the response is
+/// passed to the deserializer. A response with a status other than `2xx` is reported as
+/// [ConfigurationError::HttpStatus]. A failure to connect or to read the response is reported as
+/// [ConfigurationError::Io].
+///
+/// # Examples
+///
+/// ```no_run
+/// use serde::Deserialize;
+///
+/// #[derive(Deserialize)]
+/// struct Config {
+///     message: String
+/// }
+///
+/// let config: Config =
+///     graze::load_from_url("https://config.example.com/Config.toml", |c| toml::from_str(c))
+///         .expect("Could not load configuration");
+/// ```
+pub fn load_from_url<T, E, D>(url: &str, deserializer: D) -> Result<T, E>
+where
+    D: FnOnce(&str) -> std::result::Result<T, E>,
+{
+    let response = fetch(url, None)?;
+
+    load_from_str(&response.into_string()?, deserializer)
+}
+
+/// A configuration loaded from an HTTP(S) URL, which is only downloaded again once it changes.
+///
+/// The `ETag` of the last response is remembered, and [RemoteConfig::refresh] sends it in an
+/// `If-None-Match` header. A server which supports this responds with `304 Not Modified` and no body
+/// while the configuration is unchanged, which saves bandwidth when many machines poll the same
+/// configuration frequently. A server which does not send an `ETag` is simply asked for the whole
+/// configuration each time.
+///
+/// # Examples
+///
+/// ```no_run
+/// use std::thread;
+/// use std::time::Duration;
+///
+/// use graze::RemoteConfig;
+/// use serde::Deserialize;
+///
+/// #[derive(Deserialize)]
+/// struct Config {
+///     message: String
+/// }
+///
+/// let mut remote = RemoteConfig::load("https://config.example.com/Config.toml", |c| {
+///     toml::from_str::<Config>(c)
+/// })
+/// .expect("Could not load configuration");
+///
+/// loop {
+///     thread::sleep(Duration::from_secs(60));
+///
+///     match remote.refresh() {
+///         Ok(true) => println!("New message: {}", remote.get().message),
+///         Ok(false) => {}
+///         Err(err) => eprintln!("Could not refresh configuration: {err}"),
+///     }
+/// }
+/// ```
+pub struct RemoteConfig<T, E> {
+    value: T,
+    url: String,
+    etag: Option<String>,
+    deserializer: Deserializer<T, E>,
+}
+
+impl<T, E> RemoteConfig<T, E> {
+    /// Load the configuration from the given URL.
+    ///
+    /// See [load_from_url] for details.
+    pub fn load<D>(url: &str, deserializer: D) -> Result<Self, E>
+    where
+        D: Fn(&str) -> std::result::Result<T, E> + 'static,
+    {
+        let response = fetch(url, None)?;
+        let etag = etag(&response);
+        let value = load_from_str(&response.into_string()?, &deserializer)?;
+
+        Ok(Self {
+            value,
+            url: url.to_string(),
+            etag,
+            deserializer: Box::new(deserializer),
+        })
+    }
+
+    /// Download the configuration again unless the server reports it as unchanged, returning
+    /// whether it changed.
+    ///
+    /// If the request or deserializing the new configuration fails, the current configuration is
+    /// kept and the error is returned.
+    pub fn refresh(&mut self) -> Result<bool, E> {
+        let response = fetch(&self.url, self.etag.as_deref())?;
+
+        if response.status() == NOT_MODIFIED {
+            return Ok(false);
+        }
+
+        let etag = etag(&response);
+        self.value = load_from_str(&response.into_string()?, &self.deserializer)?;
+        self.etag = etag;
+        Ok(true)
+    }
+
+    /// Get the last downloaded configuration.
+    pub fn get(&self) -> &T {
+        &self.value
+    }
+
+    /// Get the URL of the configuration.
+    pub fn url(&self) -> &str {
+        &self.url
+    }
+
+    /// Get the `ETag` of the last downloaded configuration, if the server sent one.
+    pub fn etag(&self) -> Option<&str> {
+        self.etag.as_deref()
+    }
+
+    /// Get the configuration, discarding the URL and deserializer.
+    pub fn into_inner(self) -> T {
+        self.value
+    }
+}
+
+/// Request the configuration at `url`, sending `etag` in an `If-None-Match` header if given.
+///
+/// A response with a `2xx` status is returned, as is a `304 Not Modified` response if `etag` was
+/// given. Any other status is reported as [ConfigurationError::HttpStatus].
+fn fetch<E>(url: &str, etag: Option<&str>) -> Result<ureq::Response, E> {
+    let mut request = ureq::get(url);
+
+    if let Some(etag) = etag {
+        request = request.set("If-None-Match", etag);
+    }
+
+    let status_error = |status| ConfigurationError::HttpStatus {
+        url: url.to_string(),
+        status,
+    };
+
+    match request.call() {
+        Ok(response) if (200..300).contains(&response.status()) => Ok(response),
+        Ok(response) if etag.is_some() && response.status() == NOT_MODIFIED => Ok(response),
+        Ok(response) => Err(status_error(response.status())),
+        Err(ureq::Error::Status(status, _)) => Err(status_error(status)),
+        Err(ureq::Error::Transport(err)) => Err(io::Error::other(err).into()),
+    }
+}
+
+/// Get the `ETag` header of the given response.
+fn etag(response: &ureq::Response) -> Option<String> {
+    response.header("ETag").map(str::to_string)
+}
@@ -0,0 +1,112 @@
+//! Loading configurations split across files which include each other.
+
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use crate::{load_from_str, read_error, read_to_string, ConfigurationError, Result};
+
+/// Load a configuration from the file at the given path, along with every file it includes.
+///
+/// `extract_includes` is called with the raw content of each file and returns the paths of the
+/// files it includes, which are resolved relative to the directory of the including file. Each
+/// included file is loaded in the same way, recursively, and combined with the including file by
+/// calling `merge(including, included)`, in the order the includes were returned.
+///
+/// If a file includes itself, directly or through other files,
+/// [ConfigurationError::IncludeCycle] is returned. A file may still be included several times
+/// from different files.
+///
+/// # Examples
+///
+/// ```no_run
+/// use serde::Deserialize;
+///
+/// #[derive(Deserialize)]
+/// struct Config {
+///     #[serde(default)]
+///     include: Vec<String>,
+///     plugins: Vec<String>,
+/// }
+///
+/// let config: Config = graze::load_with_includes(
+///     "Config.toml",
+///     |c| toml::from_str(c),
+///     |c| {
+///         toml::from_str::<Config>(c)
+///             .map(|config| config.include)
+///             .unwrap_or_default()
+///     },
+///     |mut including: Config, included| {
+///         including.plugins.extend(included.plugins);
+///         including
+///     },
+/// )
+/// .expect("Could not load configuration");
+/// ```
+pub fn load_with_includes<P, T, E, D, I, M>(
+    path: P,
+    deserializer: D,
+    extract_includes: I,
+    merge: M,
+) -> Result<T, E>
+where
+    P: AsRef<Path>,
+    D: Fn(&str) -> std::result::Result<T, E>,
+    I: Fn(&str) -> Vec<String>,
+    M: Fn(T, T) -> T,
+{
+    let mut chain = Vec::new();
+    load_included(
+        path.as_ref(),
+        &deserializer,
+        &extract_includes,
+        &merge,
+        &mut chain,
+    )
+}
+
+/// Load the file at `path` and everything it includes.
+///
+/// `chain` holds the files currently being loaded, each alongside its canonical path, from the
+/// outermost file inwards.
+fn load_included<T, E, D, I, M>(
+    path: &Path,
+    deserializer: &D,
+    extract_includes: &I,
+    merge: &M,
+    chain: &mut Vec<(PathBuf, PathBuf)>,
+) -> Result<T, E>
+where
+    D: Fn(&str) -> std::result::Result<T, E>,
+    I: Fn(&str) -> Vec<String>,
+    M: Fn(T, T) -> T,
+{
+    let canonical = fs::canonicalize(path).map_err(|err| read_error(path, err))?;
+
+    if let Some(start) = chain.iter().position(|(_, c)| *c == canonical) {
+        let mut paths: Vec<_> = chain[start..].iter().map(|(p, _)| p.clone()).collect();
+        paths.push(path.to_path_buf());
+
+        return Err(ConfigurationError::IncludeCycle { paths });
+    }
+
+    let content = read_to_string(path)?;
+    let mut config = load_from_str(&content, deserializer)?;
+
+    let dir = path.parent().unwrap_or(Path::new(""));
+    chain.push((path.to_path_buf(), canonical));
+
+    for include in extract_includes(&content) {
+        let included = load_included(
+            &dir.join(include),
+            deserializer,
+            extract_includes,
+            merge,
+            chain,
+        )?;
+        config = merge(config, included);
+    }
+    chain.pop();
+
+    Ok(config)
+}
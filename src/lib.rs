@@ -1,19 +1,136 @@
 //! `graze` is a zero-boilerplate configuration library.
 //!
 //! `graze` itself does not use [serde](https://crates.io/crates/serde) as a dependency, but can
-//! easily be used alongside the `serde` ecosystem.
+//! easily be used alongside the `serde` ecosystem. The `toml`, `json` and `yaml` features provide
+//! convenience functions for the most common `serde` formats.
 //!
 //! # Functions
 //!
 //! - [load_from_path]
+//! - [check_path]
+//! - [reload_if_changed]
+//! - [load_with_raw]
+//! - [load_from_path_lenient]
+//! - [load_from_path_bytes]
+//! - [load_from_path_bounded]
+//! - [load_from_path_progress]
+//! - [load_from_path_expanded]
+//! - [load_into_env]
+//! - [load_from_reader]
+//! - [load_from_stdin]
+//! - [load_from_str]
+//! - [load_from_path_retry]
+//! - [load_from_path_timeout]
+//! - [load_from_reader_timeout]
 //! - [load_or_default]
+//! - [load_or_try_default]
+//! - [load_or_value]
+//! - [load_or_embedded]
+//! - [load_or_default_verbose]
+//! - [load_or_default_validated]
+//! - [load_or_default_on_error]
+//! - [load_boxed]
+//! - [load_or_default_boxed]
+//! - [load_or_write_default_boxed]
 //! - [load_or_write_default]
+//! - [load_with_policy]
+//! - [load_or_write_default_durable]
+//! - [load_or_write_template]
+//! - [write_template]
+//! - [load_or_write_default_bytes]
+//! - [load_or_write_default_trailing_newline]
+//! - [load_from_path_in]
+//! - [load_or_default_in]
+//! - [load_or_write_default_in]
+//! - [load_or_write_default_durable_in]
+//! - [load_from_first_existing]
+//! - [load_trying]
+//! - [load_profile]
+//! - [load_with_overrides]
+//! - [load_into]
+//! - [load_validated]
+//! - [load_with_migration]
+//! - [load_with_migration_persisted]
+//! - [load_or_partial]
+//! - [load_or_partial_with_report]
+//! - [load_merged]
+//! - [load_dir]
+//! - [load_dir_with_extensions]
+//! - [load_dir_merged]
+//! - [load_with_includes]
+//! - [load_from_path_with_meta]
+//! - [load_from_path_with_mtime]
+//! - [load_from_first_existing_with_meta]
+//! - [load_or_write_default_with_meta]
+//! - [load_or_write_default_with_callback]
+//! - [load_or_write_default_dry_run]
+//! - [is_first_run]
+//! - [load_or_insert_default]
+//! - [load_from_path_hashed]
+//! - [file_matches_hash]
+//! - [diff_serialized]
+//! - [drift_report]
+//! - [load_or_write_default_with_mode]
+//! - [load_from_path_with_options]
+//! - [load_or_write_default_with_options]
+//! - [save_to_path_with_options]
+//! - [save_to_path]
+//! - [write_default]
+//! - [save_to_path_with_backup]
+//! - [save_to_path_with_line_endings]
+//! - [save_if_changed]
+//! - [persist]
+//! - [verify_writable]
+//!
+//! The behaviors of these functions can also be combined using [ConfigLoader].
+//!
+//! # Features
+//!
+//! - `bincode`: convenience functions for [bincode](https://crates.io/crates/bincode) files.
+//! - `dirs`: resolving platform configuration directories and expanding `~` in paths, backed by
+//!   [directories](https://crates.io/crates/directories).
+//! - `encoding`: loading configuration files in encodings other than UTF-8, backed by
+//!   [encoding_rs](https://crates.io/crates/encoding_rs).
+//! - `error-snippets`: formatting deserializer errors along with the line they occurred in.
+//! - `flat`: loading flat `key = value` configurations without a `serde` format, and layering
+//!   `.env` style files over configurations.
+//! - `gzip`: transparently decompressing and compressing gzip configuration files, backed by
+//!   [flate2](https://crates.io/crates/flate2).
+//! - `http`: loading configurations from HTTP(S) URLs, and refreshing them only once they change,
+//!   backed by [ureq](https://crates.io/crates/ureq).
+//! - `json`: convenience functions and the `Json` format for
+//!   [JSON](https://crates.io/crates/serde_json) files.
+//! - `lock`: holding an advisory lock on a configuration file, backed by
+//!   [fs4](https://crates.io/crates/fs4).
+//! - `log`: logging the paths tried and the defaults used or written while loading configurations,
+//!   backed by [log](https://crates.io/crates/log).
+//! - `multi-format`: loading configurations in a format chosen by their file extension, enabling
+//!   the `json`, `toml` and `yaml` features.
+//! - `no-follow`: loading configuration files while refusing to follow a symbolic link at their
+//!   path on Unix, backed by [libc](https://crates.io/crates/libc).
+//! - `schema`: exporting a JSON Schema of a configuration type for editors, backed by
+//!   [schemars](https://crates.io/crates/schemars).
+//! - `signal`: reloading a [SharedConfig] when the process receives `SIGHUP` on Unix, backed by
+//!   [signal-hook](https://crates.io/crates/signal-hook).
+//! - `tokio`: asynchronous variants of the loading functions, backed by
+//!   [tokio](https://crates.io/crates/tokio).
+//! - `toml`: convenience functions and the `Toml` format for
+//!   [TOML](https://crates.io/crates/toml) files.
+//! - `toml-edit`: editing TOML files while preserving their comments and formatting, backed by
+//!   [toml_edit](https://crates.io/crates/toml_edit).
+//! - `watch`: reloading configurations when their file changes, backed by
+//!   [notify](https://crates.io/crates/notify).
+//! - `windows-acl`: restricting configuration files written by
+//!   [load_or_write_default_with_mode] to the current user on Windows, backed by
+//!   [windows-sys](https://crates.io/crates/windows-sys).
+//! - `yaml`: convenience functions and the `Yaml` format for
+//!   [YAML](https://crates.io/crates/serde_yaml) files.
 //!
 //! # Examples
 //!
 //! ## Load a configuration using the [toml](https://crates.io/crates/toml) crate
 //!
-//! ```
+//! ```no_run
 //! use serde::Deserialize;
 //!
 //! #[derive(Deserialize)]
@@ -27,61 +144,411 @@
 //! println!("{}", config.message);
 //! ```
 
+use std::ffi::OsString;
 use std::fmt::{Debug, Display, Formatter};
-use std::path::Path;
-use std::{fmt, fs, io};
+use std::fs::OpenOptions;
+use std::io::{BufRead, BufReader, Read, Write};
+use std::path::{Path, PathBuf};
+use std::{fmt, fs, io, process};
 
 use thiserror::Error;
 
+#[cfg(feature = "tokio")]
+pub use asynchronous::*;
+pub use boxed::*;
+pub use cache::*;
+pub use diff::*;
+pub use directory::*;
+#[cfg(feature = "dirs")]
+pub use dirs::*;
+#[cfg(feature = "toml-edit")]
+pub use edit::*;
+#[cfg(feature = "encoding")]
+pub use encoding::*;
+pub use env::*;
+#[cfg(feature = "flat")]
+pub use flat::*;
+#[cfg(any(
+    feature = "bincode",
+    feature = "json",
+    feature = "toml",
+    feature = "yaml"
+))]
+pub use formats::*;
+pub use handle::*;
+pub use hash::*;
+#[cfg(feature = "http")]
+pub use http::*;
+pub use include::*;
+pub use line_endings::*;
+pub use loader::*;
+#[cfg(feature = "lock")]
+pub use lock::*;
+pub use meta::*;
+pub use options::*;
+pub use permissions::*;
+pub use persist::*;
+pub use policy::*;
+pub use retry::*;
+#[cfg(feature = "schema")]
+pub use schema::*;
+pub use shared::*;
+#[cfg(all(unix, feature = "signal"))]
+pub use signal::*;
+#[cfg(feature = "error-snippets")]
+pub use snippet::*;
+pub use timeout::*;
+pub use vfs::*;
+#[cfg(feature = "watch")]
+pub use watch::*;
+
+/// Emit a debug message through [log] with the `log` feature, or do nothing without it.
+macro_rules! debug {
+    ($($arg:tt)+) => {
+        #[cfg(feature = "log")]
+        log::debug!($($arg)+)
+    };
+}
+
+/// Emit a trace message through [log] with the `log` feature, or do nothing without it.
+macro_rules! trace {
+    ($($arg:tt)+) => {
+        #[cfg(feature = "log")]
+        log::trace!($($arg)+)
+    };
+}
+
+/// Emit a warning through [log] with the `log` feature, or do nothing without it.
+#[cfg(all(unix, feature = "signal"))]
+macro_rules! warn {
+    ($($arg:tt)+) => {
+        #[cfg(feature = "log")]
+        log::warn!($($arg)+)
+    };
+}
+
+#[cfg(all(windows, feature = "windows-acl"))]
+mod acl;
+#[cfg(feature = "tokio")]
+mod asynchronous;
+mod boxed;
+mod cache;
+mod diff;
+mod directory;
+#[cfg(feature = "dirs")]
+mod dirs;
+#[cfg(feature = "toml-edit")]
+mod edit;
+#[cfg(feature = "encoding")]
+mod encoding;
+mod env;
+#[cfg(feature = "flat")]
+mod flat;
+#[cfg(any(
+    feature = "bincode",
+    feature = "json",
+    feature = "toml",
+    feature = "yaml"
+))]
+mod formats;
+#[cfg(feature = "gzip")]
+mod gzip;
+mod handle;
+mod hash;
+#[cfg(feature = "http")]
+mod http;
+mod include;
+mod line_endings;
+mod loader;
+#[cfg(feature = "lock")]
+mod lock;
+mod meta;
+mod options;
+mod permissions;
+mod persist;
+mod policy;
+mod retry;
+#[cfg(feature = "schema")]
+mod schema;
+mod shared;
+#[cfg(all(unix, feature = "signal"))]
+mod signal;
+#[cfg(feature = "error-snippets")]
+mod snippet;
 #[cfg(test)]
 mod tests;
+mod timeout;
+mod vfs;
+#[cfg(feature = "watch")]
+mod watch;
 
 /// The error type returned by functions which return a [Result].
+///
+/// [std::error::Error] is implemented whenever `E` implements it, with [Error::source] returning
+/// the underlying [io::Error] or deserializer error.
+///
+/// [Error::source]: std::error::Error::source
 #[derive(Error)]
 pub enum ConfigurationError<E> {
     /// An IO error occurred.
-    Io(#[from] io::Error),
+    #[error(
+        "An error occurred while opening the configuration file{}: {source}",
+        display_path(path)
+    )]
+    Io {
+        /// The path of the file being accessed, if known.
+        path: Option<PathBuf>,
+
+        /// The underlying error.
+        #[source]
+        source: io::Error,
+    },
+
+    /// The configuration file does not exist.
+    #[error("No configuration file was found at: {}", display_paths(paths))]
+    NotFound {
+        /// The paths that were tried.
+        paths: Vec<PathBuf>,
+    },
 
     /// The deserializer returned an error.
-    Deserialize(E),
+    #[error("Configuration file is incorrect: {0}")]
+    Deserialize(#[source] E),
+
+    /// The serializer returned an error.
+    #[error("Could not serialize configuration: {0}")]
+    Serialize(#[source] E),
+
+    /// The function computing the default configuration returned an error.
+    #[error("Could not compute the default configuration: {0}")]
+    Default(#[source] E),
+
+    /// The deserializer returned an error for one of several files being loaded.
+    #[error("Configuration file '{}' is incorrect: {source}", path.display())]
+    DeserializeFile {
+        /// The path of the file that could not be deserialized.
+        path: PathBuf,
+
+        /// The deserializer error.
+        #[source]
+        source: E,
+    },
+
+    /// A configuration file includes itself, directly or through other files.
+    #[error("Configuration files include each other: {}", display_paths(paths))]
+    IncludeCycle {
+        /// The files forming the cycle, starting and ending with the same file.
+        paths: Vec<PathBuf>,
+    },
+
+    /// The configuration file references an environment variable which is not set.
+    #[error("The environment variable '{name}' referenced by the configuration file is not set")]
+    UndefinedVariable {
+        /// The name of the environment variable.
+        name: String,
+    },
+
+    /// The configuration file is locked by another process.
+    #[error("The configuration file '{}' is locked by another process", path.display())]
+    Locked {
+        /// The path of the locked file.
+        path: PathBuf,
+    },
+
+    /// The configuration file is larger than the maximum size allowed.
+    #[error(
+        "The configuration file '{}' is {size} bytes, larger than the limit of {limit} bytes",
+        path.display()
+    )]
+    TooLarge {
+        /// The path of the file.
+        path: PathBuf,
+
+        /// The size of the file, in bytes. If the file grew while it was read, this is the number
+        /// of bytes read before the limit was exceeded.
+        size: u64,
+
+        /// The maximum size allowed, in bytes.
+        limit: u64,
+    },
+
+    /// The path of the configuration file is a directory.
+    #[error(
+        "The configuration path '{}' is a directory; pass the path of a configuration file instead",
+        path.display()
+    )]
+    IsDirectory {
+        /// The path of the directory.
+        path: PathBuf,
+    },
+
+    /// The server responded to a request for a configuration with a status other than success.
+    #[error("The server at '{url}' responded with HTTP status {status}")]
+    HttpStatus {
+        /// The URL of the configuration.
+        url: String,
+
+        /// The HTTP status code of the response.
+        status: u16,
+    },
 }
 
-impl<E> Display for ConfigurationError<E>
+impl<E> Debug for ConfigurationError<E>
 where
     E: Display,
 {
     fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
         match self {
-            Self::Io(err) => {
-                write!(
-                    f,
-                    "An error occurred while opening the configuration file: {err}"
-                )
+            Self::Io {
+                path: Some(path),
+                source,
+            } => write!(f, "Io({}: {source})", path.display()),
+            Self::Io { path: None, source } => write!(f, "Io({source})"),
+            Self::NotFound { paths } => write!(f, "NotFound({paths:?})"),
+            Self::Deserialize(err) => write!(f, "Deserialize({err})"),
+            Self::Serialize(err) => write!(f, "Serialize({err})"),
+            Self::Default(err) => write!(f, "Default({err})"),
+            Self::DeserializeFile { path, source } => {
+                write!(f, "DeserializeFile({}: {source})", path.display())
             }
-            Self::Deserialize(err) => {
-                write!(f, "Configuration file is incorrect: {err}")
+            Self::IncludeCycle { paths } => write!(f, "IncludeCycle({paths:?})"),
+            Self::UndefinedVariable { name } => write!(f, "UndefinedVariable({name})"),
+            Self::Locked { path } => write!(f, "Locked({})", path.display()),
+            Self::TooLarge { path, size, limit } => {
+                write!(f, "TooLarge({}: {size} > {limit})", path.display())
             }
+            Self::IsDirectory { path } => write!(f, "IsDirectory({})", path.display()),
+            Self::HttpStatus { url, status } => write!(f, "HttpStatus({url}: {status})"),
         }
     }
 }
 
-impl<E> Debug for ConfigurationError<E>
+impl<E> From<io::Error> for ConfigurationError<E> {
+    fn from(source: io::Error) -> Self {
+        Self::Io { path: None, source }
+    }
+}
+
+/// Collapse a [ConfigurationError] into an [io::Error], e.g. for use in functions which return
+/// [io::Result].
+///
+/// [ConfigurationError::Io] is converted to the underlying error, [ConfigurationError::NotFound] to
+/// [io::ErrorKind::NotFound], [ConfigurationError::Locked] to [io::ErrorKind::WouldBlock] and
+/// [ConfigurationError::IsDirectory] to [io::ErrorKind::IsADirectory] and
+/// [ConfigurationError::Default] to [io::ErrorKind::Other]. Every other variant is converted to
+/// [io::ErrorKind::InvalidData]. Apart from the underlying error of
+/// [ConfigurationError::Io], the message of the new error is the message of the
+/// [ConfigurationError].
+impl<E> From<ConfigurationError<E>> for io::Error
 where
     E: Display,
 {
-    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+    fn from(err: ConfigurationError<E>) -> Self {
+        let kind = match err {
+            ConfigurationError::Io { source, .. } => return source,
+            ConfigurationError::NotFound { .. } => io::ErrorKind::NotFound,
+            ConfigurationError::Locked { .. } => io::ErrorKind::WouldBlock,
+            ConfigurationError::IsDirectory { .. } => io::ErrorKind::IsADirectory,
+            ConfigurationError::Default(_) => io::ErrorKind::Other,
+            _ => io::ErrorKind::InvalidData,
+        };
+        io::Error::new(kind, err.to_string())
+    }
+}
+
+impl<E> ConfigurationError<E> {
+    /// Create a [ConfigurationError::Io] from an error which occurred while accessing the file at
+    /// `path`.
+    pub(crate) fn io(path: &Path, source: io::Error) -> Self {
+        Self::Io {
+            path: Some(path.to_path_buf()),
+            source,
+        }
+    }
+
+    /// Create a [ConfigurationError::Deserialize] from the given deserializer error.
+    ///
+    /// This is useful for converting a deserializer error with [Result::map_err].
+    pub fn deserialize(err: E) -> Self {
+        Self::Deserialize(err)
+    }
+
+    /// Create a [ConfigurationError::Serialize] from the given serializer error.
+    ///
+    /// This is useful for converting a serializer error with [Result::map_err].
+    pub fn serialize(err: E) -> Self {
+        Self::Serialize(err)
+    }
+
+    /// Convert the deserializer, serializer or default error using the given function, leaving
+    /// other errors untouched.
+    ///
+    /// This is useful for unifying errors returned by different deserializers.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use graze::ConfigurationError;
+    ///
+    /// let err = graze::load_from_str::<u32, _, _>("range = ", |s| s.parse::<u32>())
+    ///     .expect_err("Invalid content should fail");
+    ///
+    /// let err: ConfigurationError<String> = err.map_deserialize(|e| e.to_string());
+    /// ```
+    pub fn map_deserialize<F, E2>(self, f: F) -> ConfigurationError<E2>
+    where
+        F: FnOnce(E) -> E2,
+    {
         match self {
-            Self::Io(err) => write!(f, "Io({err})"),
-            Self::Deserialize(err) => write!(f, "Deserialize({err}"),
+            Self::Io { path, source } => ConfigurationError::Io { path, source },
+            Self::NotFound { paths } => ConfigurationError::NotFound { paths },
+            Self::Deserialize(err) => ConfigurationError::Deserialize(f(err)),
+            Self::Serialize(err) => ConfigurationError::Serialize(f(err)),
+            Self::Default(err) => ConfigurationError::Default(f(err)),
+            Self::DeserializeFile { path, source } => ConfigurationError::DeserializeFile {
+                path,
+                source: f(source),
+            },
+            Self::IncludeCycle { paths } => ConfigurationError::IncludeCycle { paths },
+            Self::UndefinedVariable { name } => ConfigurationError::UndefinedVariable { name },
+            Self::Locked { path } => ConfigurationError::Locked { path },
+            Self::TooLarge { path, size, limit } => {
+                ConfigurationError::TooLarge { path, size, limit }
+            }
+            Self::IsDirectory { path } => ConfigurationError::IsDirectory { path },
+            Self::HttpStatus { url, status } => ConfigurationError::HttpStatus { url, status },
         }
     }
 }
 
+/// Format an optional path as a quoted path preceded by a space, or nothing if there is no path.
+fn display_path(path: &Option<PathBuf>) -> String {
+    match path {
+        Some(path) => format!(" '{}'", path.display()),
+        None => String::new(),
+    }
+}
+
+/// Format a list of paths as a comma-separated list of quoted paths.
+fn display_paths(paths: &[PathBuf]) -> String {
+    paths
+        .iter()
+        .map(|p| format!("'{}'", p.display()))
+        .collect::<Vec<_>>()
+        .join(", ")
+}
+
 pub type Result<T, E> = std::result::Result<T, ConfigurationError<E>>;
 
 /// Load a configuration from the file at the given path.
 ///
-/// ```
+/// If the file does not exist, [ConfigurationError::NotFound] is returned. Any other failure to
+/// read the file is returned as [ConfigurationError::Io].
+///
+/// With the `gzip` feature, a file starting with the gzip magic bytes is decompressed before being
+/// deserialized, as is done by every function reading a configuration file from a path.
+///
+/// ```no_run
 /// use serde::Deserialize;
 ///
 /// #[derive(Deserialize)]
@@ -99,91 +566,1898 @@ where
     P: AsRef<Path>,
     D: FnOnce(&str) -> std::result::Result<T, E>,
 {
-    let content = fs::read_to_string(path)?;
-    deserializer(&content).map_err(|e| ConfigurationError::Deserialize(e))
+    load_with_policy(path, deserializer, MissingPolicy::Error)
 }
 
-/// Load a configuration from the file at the given path, or use the default value if the file does
-/// not exist.
+/// Check that the file at the given path contains a valid configuration, discarding the
+/// configuration.
+///
+/// This is useful for e.g. a command which validates a configuration file before it is used. Any
+/// error which [load_from_path] would return is returned.
 ///
 /// # Examples
 ///
-/// ```
+/// ```no_run
 /// use serde::Deserialize;
 ///
-/// #[derive(Deserialize, Default)]
+/// #[derive(Deserialize)]
 /// struct Config {
 ///     message: String
 /// }
 ///
-/// let config = graze::load_or_default("Config.toml", |s| toml::from_str(s), Config::default);
+/// match graze::check_path("Config.toml", |c| toml::from_str::<Config>(c)) {
+///     Ok(()) => println!("The configuration is valid"),
+///     Err(err) => {
+///         eprintln!("{err}");
+///         std::process::exit(1);
+///     }
+/// }
 /// ```
-pub fn load_or_default<P, T, E, D, F>(path: P, deserializer: D, default: F) -> Result<T, E>
+pub fn check_path<P, T, E, D>(path: P, deserializer: D) -> Result<(), E>
 where
     P: AsRef<Path>,
     D: FnOnce(&str) -> std::result::Result<T, E>,
-    F: FnOnce() -> T,
 {
-    let path = path.as_ref();
-
-    if path.exists() {
-        return load_from_path(path, deserializer);
-    }
-    Ok(default())
+    load_from_path(path, deserializer).map(drop)
 }
 
-/// Load a configuration from the file at the given path, or use the default value if the file does
-/// not exist.
+/// Load the configuration from the file at the given path again, returning it only if it differs
+/// from the previously loaded configuration.
 ///
-/// If the file does not exist, the default value will be written to the file at the given path.
+/// Returns [None] if the newly loaded configuration is equal to `previous`, even if the content of
+/// the file changed (e.g. because only comments or the order of keys changed). This is useful for
+/// avoiding work, such as restarting parts of a program, when a reload has no effect.
 ///
 /// # Examples
 ///
+/// ```no_run
+/// use serde::Deserialize;
+///
+/// #[derive(Deserialize, PartialEq)]
+/// struct Config {
+///     message: String
+/// }
+///
+/// let mut config: Config = graze::load_from_path("Config.toml", |c| toml::from_str(c))
+///     .expect("Could not load configuration");
+///
+/// if let Some(new) = graze::reload_if_changed("Config.toml", |c| toml::from_str(c), &config)
+///     .expect("Could not reload configuration")
+/// {
+///     config = new;
+///     println!("The configuration changed, new message: {}", config.message);
+/// }
 /// ```
-/// use serde::{Serialize, Deserialize};
+pub fn reload_if_changed<P, T, E, D>(path: P, deserializer: D, previous: &T) -> Result<Option<T>, E>
+where
+    P: AsRef<Path>,
+    T: PartialEq,
+    D: FnOnce(&str) -> std::result::Result<T, E>,
+{
+    let config = load_from_path(path, deserializer)?;
+    Ok((config != *previous).then_some(config))
+}
+
+/// Load a configuration from the file at the given path, refusing files larger than `max_bytes`.
 ///
-/// #[derive(Serialize, Deserialize)]
+/// The size of the file is checked before it is read, and the file is read into a buffer of that
+/// size, avoiding reallocations for large files. If the file is larger than `max_bytes`, or grows
+/// beyond it while being read, [ConfigurationError::TooLarge] is returned without reading the rest
+/// of the file. This guards against accidentally loading e.g. a huge log file as a configuration.
+///
+/// # Examples
+///
+/// ```no_run
+/// use serde::Deserialize;
+///
+/// #[derive(Deserialize)]
 /// struct Config {
 ///     message: String
 /// }
 ///
-/// impl Default for Config {
-///     fn default() -> Self {
-///         Self { message: "Hello, world!".to_string() }
-///     }
+/// let config: Config =
+///     graze::load_from_path_bounded("Config.toml", |c| toml::from_str(c), 1024 * 1024)
+///         .expect("Could not load configuration");
+/// ```
+pub fn load_from_path_bounded<P, T, E, D>(path: P, deserializer: D, max_bytes: u64) -> Result<T, E>
+where
+    P: AsRef<Path>,
+    D: FnOnce(&str) -> std::result::Result<T, E>,
+{
+    let content = read_to_string_bounded(path.as_ref(), max_bytes)?;
+
+    load_from_str(&content, deserializer)
+}
+
+/// Load a configuration from the file at the given path, reporting the progress of reading it.
+///
+/// The file is read in chunks, and `on_progress` is called after each chunk with the number of
+/// bytes read so far and the total size of the file, taken from its metadata before reading. This
+/// is useful for showing progress while loading a configuration file of several megabytes, e.g. on
+/// a splash screen. The content is deserialized once the whole file has been read. See
+/// [load_from_path] for details.
+///
+/// # Examples
+///
+/// ```no_run
+/// use serde::Deserialize;
+///
+/// #[derive(Deserialize)]
+/// struct Config {
+///     message: String
 /// }
 ///
-/// // If Config.toml exists, deserialize the content using toml::from_str.
-/// //
-/// // If the file does not exist, use the value returned from Config::default(), serialize the
-/// // value, and save the serialized data to Config.toml.
-/// let config = graze::load_or_write_default("Config.toml",
-///     |s| toml::from_str(s),
-///     |c| toml::to_string(&c).unwrap(),
-///     Config::default
-/// );
+/// let config: Config = graze::load_from_path_progress(
+///     "Config.toml",
+///     |c| toml::from_str(c),
+///     |read, total| println!("Loading configuration: {read}/{total} bytes"),
+/// )
+/// .expect("Could not load configuration");
 /// ```
-pub fn load_or_write_default<P, T, E, D, S, F, B>(
+pub fn load_from_path_progress<P, T, E, D, F>(
     path: P,
     deserializer: D,
-    serializer: S,
-    default: F,
+    on_progress: F,
 ) -> Result<T, E>
 where
     P: AsRef<Path>,
     D: FnOnce(&str) -> std::result::Result<T, E>,
-    S: FnOnce(&T) -> B,
-    B: AsRef<[u8]>,
-    F: FnOnce() -> T,
+    F: FnMut(u64, u64),
 {
-    let path = path.as_ref();
+    let content = read_to_string_progress(path.as_ref(), on_progress)?;
 
-    if path.exists() {
-        return load_from_path(path, deserializer);
-    }
+    load_from_str(&content, deserializer)
+}
 
-    let data = default();
-    fs::write(path, serializer(&data))?;
+/// Load a configuration from the file at the given path, along with the content of the file.
+///
+/// The file is read only once; its content is passed to the deserializer and then returned
+/// alongside the configuration. This is useful for showing the configuration as it was written,
+/// including its comments and formatting. See [load_from_path] for details.
+///
+/// # Examples
+///
+/// ```no_run
+/// use serde::Deserialize;
+///
+/// #[derive(Deserialize)]
+/// struct Config {
+///     message: String
+/// }
+///
+/// let (config, raw): (Config, _) = graze::load_with_raw("Config.toml", |c| toml::from_str(c))
+///     .expect("Could not load configuration");
+///
+/// println!("Current configuration:\n{raw}");
+/// ```
+pub fn load_with_raw<P, T, E, D>(path: P, deserializer: D) -> Result<(T, String), E>
+where
+    P: AsRef<Path>,
+    D: FnOnce(&str) -> std::result::Result<T, E>,
+{
+    let content = read_to_string(path.as_ref())?;
+    let config = load_from_str(&content, deserializer)?;
 
-    Ok(data)
+    Ok((config, content))
+}
+
+/// Load a configuration from the raw bytes of the file at the given path.
+///
+/// Unlike [load_from_path], the content does not need to be valid UTF-8, which allows binary
+/// formats (e.g. [bincode](https://crates.io/crates/bincode)) to be used. Errors are reported in
+/// the same way as [load_from_path].
+///
+/// # Examples
+///
+/// ```no_run
+/// let config: Vec<u8> = graze::load_from_path_bytes("Config.bin", |c| {
+///     Ok::<_, std::convert::Infallible>(c.to_vec())
+/// })
+/// .expect("Could not load configuration");
+/// ```
+pub fn load_from_path_bytes<P, T, E, D>(path: P, deserializer: D) -> Result<T, E>
+where
+    P: AsRef<Path>,
+    D: FnOnce(&[u8]) -> std::result::Result<T, E>,
+{
+    let content = read_bytes(path.as_ref())?;
+
+    deserializer(&content).map_err(ConfigurationError::deserialize)
+}
+
+/// The byte order mark which some editors prepend to UTF-8 files.
+const BOM: char = '\u{FEFF}';
+
+/// The maximum number of names tried for a temporary file before giving up.
+const TEMP_ATTEMPTS: u32 = 100;
+
+/// Load a configuration from the file at the given path, ignoring a leading UTF-8 byte order mark.
+///
+/// Some editors (notably on Windows) prepend a byte order mark to the files they save, which most
+/// deserializers reject. This function behaves like [load_from_path], except that a byte order mark
+/// at the start of the file is removed before the content is passed to the deserializer. The file
+/// itself is left untouched.
+///
+/// # Examples
+///
+/// ```no_run
+/// use serde::Deserialize;
+///
+/// #[derive(Deserialize)]
+/// struct Config {
+///     message: String
+/// }
+///
+/// let config: Config = graze::load_from_path_lenient("Config.toml", |c| toml::from_str(c))
+///     .expect("Could not load configuration");
+/// ```
+pub fn load_from_path_lenient<P, T, E, D>(path: P, deserializer: D) -> Result<T, E>
+where
+    P: AsRef<Path>,
+    D: FnOnce(&str) -> std::result::Result<T, E>,
+{
+    let content = read_to_string(path.as_ref())?;
+    let content = content.strip_prefix(BOM).unwrap_or(&content);
+
+    load_from_str(content, deserializer)
+}
+
+/// Load a configuration from the given reader.
+///
+/// The reader is read to the end before the content is passed to the deserializer. Content that is
+/// not valid UTF-8 is reported as [ConfigurationError::Io].
+///
+/// # Examples
+///
+/// ```
+/// use std::io::Cursor;
+///
+/// use serde::Deserialize;
+///
+/// #[derive(Deserialize)]
+/// struct Config {
+///     message: String
+/// }
+///
+/// let reader = Cursor::new(b"message = 'Hello, world!'");
+///
+/// let config: Config = graze::load_from_reader(reader, |c| toml::from_str(c))
+///     .expect("Could not load configuration");
+///
+/// assert_eq!(config.message, "Hello, world!");
+/// ```
+pub fn load_from_reader<R, T, E, D>(mut reader: R, deserializer: D) -> Result<T, E>
+where
+    R: Read,
+    D: FnOnce(&str) -> std::result::Result<T, E>,
+{
+    let mut content = String::new();
+    reader.read_to_string(&mut content)?;
+
+    load_from_str(&content, deserializer)
+}
+
+/// Load a configuration from the standard input.
+///
+/// This is useful for supporting the common convention of passing `-` as the configuration path
+/// to read the configuration from a pipeline. Standard input is read to the end, in the same way
+/// as [load_from_reader]; code which should be testable without a real standard input can accept
+/// a reader and pass it to [load_from_reader] instead.
+///
+/// # Examples
+///
+/// ```no_run
+/// use serde::Deserialize;
+///
+/// #[derive(Deserialize)]
+/// struct Config {
+///     message: String
+/// }
+///
+/// let path = std::env::args().nth(1).unwrap_or_else(|| "Config.toml".to_string());
+///
+/// let config: Config = if path == "-" {
+///     graze::load_from_stdin(|c| toml::from_str(c))
+/// } else {
+///     graze::load_from_path(&path, |c| toml::from_str(c))
+/// }
+/// .expect("Could not load configuration");
+/// ```
+pub fn load_from_stdin<T, E, D>(deserializer: D) -> Result<T, E>
+where
+    D: FnOnce(&str) -> std::result::Result<T, E>,
+{
+    load_from_reader(io::stdin().lock(), deserializer)
+}
+
+/// Load a configuration from content that is already in memory.
+///
+/// This is useful for configurations passed inline (e.g. through a command line flag), as errors
+/// are reported through the same [ConfigurationError] as configurations loaded from files.
+///
+/// # Examples
+///
+/// ```
+/// use serde::Deserialize;
+///
+/// #[derive(Deserialize)]
+/// struct Config {
+///     message: String
+/// }
+///
+/// let config: Config = graze::load_from_str("message = 'Hello, world!'", |c| toml::from_str(c))
+///     .expect("Could not load configuration");
+///
+/// assert_eq!(config.message, "Hello, world!");
+/// ```
+pub fn load_from_str<T, E, D>(content: &str, deserializer: D) -> Result<T, E>
+where
+    D: FnOnce(&str) -> std::result::Result<T, E>,
+{
+    deserializer(content).map_err(ConfigurationError::deserialize)
+}
+
+/// Load a configuration from the first of the given paths that exists.
+///
+/// The candidates are tried in order. If a candidate exists but cannot be loaded, that error is
+/// returned immediately rather than falling through to the next candidate. If none of the
+/// candidates exist, [ConfigurationError::NotFound] is returned listing every path that was tried.
+///
+/// # Examples
+///
+/// ```no_run
+/// use serde::Deserialize;
+///
+/// #[derive(Deserialize)]
+/// struct Config {
+///     message: String
+/// }
+///
+/// let config: Config = graze::load_from_first_existing(
+///     ["Config.toml", "/etc/myapp/Config.toml"],
+///     |c| toml::from_str(c),
+/// )
+/// .expect("Could not load configuration");
+/// ```
+pub fn load_from_first_existing<I, P, T, E, D>(paths: I, deserializer: D) -> Result<T, E>
+where
+    I: IntoIterator<Item = P>,
+    P: AsRef<Path>,
+    D: FnOnce(&str) -> std::result::Result<T, E>,
+{
+    load_from_first_existing_with_meta(paths, deserializer).map(|(config, _)| config)
+}
+
+/// Load a configuration from the file at the given path, trying each of the given deserializers in
+/// order.
+///
+/// The file is read only once, and its content is passed to each deserializer in turn until one of
+/// them succeeds. If every deserializer fails, the error returned by the last one is propagated
+/// through [ConfigurationError::Deserialize]. This is useful for files whose format is not known in
+/// advance, e.g. while migrating from one format to another.
+///
+/// If `deserializers` is empty, [ConfigurationError::Io] is returned with
+/// [io::ErrorKind::InvalidInput], without reading the file.
+///
+/// # Examples
+///
+/// ```no_run
+/// use serde::Deserialize;
+///
+/// #[derive(Deserialize)]
+/// struct Config {
+///     message: String
+/// }
+///
+/// type Deserializer = Box<dyn Fn(&str) -> Result<Config, toml::de::Error>>;
+///
+/// // Older versions stored only the message, without any TOML syntax.
+/// let legacy = |s: &str| Ok(Config { message: s.trim().to_string() });
+///
+/// let config = graze::load_trying(
+///     "Config.conf",
+///     vec![Box::new(|s: &str| toml::from_str(s)) as Deserializer, Box::new(legacy)],
+/// )
+/// .expect("Could not load configuration");
+/// ```
+pub fn load_trying<P, T, E, D>(path: P, deserializers: Vec<D>) -> Result<T, E>
+where
+    P: AsRef<Path>,
+    D: FnOnce(&str) -> std::result::Result<T, E>,
+{
+    let path = path.as_ref();
+    let mut deserializers = deserializers.into_iter();
+
+    let Some(first) = deserializers.next() else {
+        return Err(ConfigurationError::io(
+            path,
+            io::Error::new(io::ErrorKind::InvalidInput, "No deserializers were given"),
+        ));
+    };
+
+    let content = read_to_string(path)?;
+    let mut result = first(&content);
+
+    for deserializer in deserializers {
+        if result.is_ok() {
+            break;
+        }
+        result = deserializer(&content);
+    }
+
+    result.map_err(ConfigurationError::deserialize)
+}
+
+/// Load one profile of the configuration in the file at the given path.
+///
+/// The deserializer receives the content of the file along with the name of the profile, and is
+/// expected to deserialize only the section of the content belonging to that profile. This is
+/// useful for files which hold a configuration for each environment the program runs in, e.g.
+/// `[dev]` and `[prod]` tables in a TOML file.
+///
+/// # Examples
+///
+/// ```no_run
+/// use std::collections::HashMap;
+///
+/// use serde::Deserialize;
+///
+/// #[derive(Deserialize)]
+/// struct Config {
+///     message: String
+/// }
+///
+/// let config = graze::load_profile("Config.toml", "prod", |s, profile| {
+///     let mut profiles: HashMap<String, Config> = toml::from_str(s).map_err(|e| e.to_string())?;
+///     profiles
+///         .remove(profile)
+///         .ok_or_else(|| format!("The profile '{profile}' does not exist"))
+/// })
+/// .expect("Could not load configuration");
+/// ```
+pub fn load_profile<P, T, E, D>(path: P, profile: &str, deserializer: D) -> Result<T, E>
+where
+    P: AsRef<Path>,
+    D: FnOnce(&str, &str) -> std::result::Result<T, E>,
+{
+    load_from_path(path, |content| deserializer(content, profile))
+}
+
+/// Load a configuration from the file at the given path, then apply overrides to it.
+///
+/// The overrides closure receives the deserialized configuration mutably, e.g. to replace fields
+/// with values taken from environment variables. Overrides only run when the configuration was
+/// loaded successfully. An error returned by the overrides closure is propagated through
+/// [ConfigurationError::Deserialize].
+///
+/// # Examples
+///
+/// ```no_run
+/// use serde::Deserialize;
+///
+/// #[derive(Deserialize)]
+/// struct Config {
+///     message: String
+/// }
+///
+/// let config: Config = graze::load_with_overrides(
+///     "Config.toml",
+///     |c| toml::from_str(c),
+///     |c: &mut Config| {
+///         if let Ok(message) = std::env::var("MYAPP_MESSAGE") {
+///             c.message = message;
+///         }
+///         Ok(())
+///     },
+/// )
+/// .expect("Could not load configuration");
+/// ```
+pub fn load_with_overrides<P, T, E, D, O>(path: P, deserializer: D, overrides: O) -> Result<T, E>
+where
+    P: AsRef<Path>,
+    D: FnOnce(&str) -> std::result::Result<T, E>,
+    O: FnOnce(&mut T) -> std::result::Result<(), E>,
+{
+    let mut config = load_from_path(path, deserializer)?;
+    overrides(&mut config).map_err(ConfigurationError::deserialize)?;
+
+    Ok(config)
+}
+
+/// Load a configuration from the file at the given path, then validate it.
+///
+/// The validator only runs when the configuration was deserialized successfully. An error returned
+/// by the validator is propagated through [ConfigurationError::Deserialize], so that validation
+/// failures can be handled in the same way as parse failures.
+///
+/// # Examples
+///
+/// ```no_run
+/// use serde::Deserialize;
+///
+/// #[derive(Deserialize)]
+/// struct Config {
+///     port: u16
+/// }
+///
+/// let config: Config = graze::load_validated(
+///     "Config.toml",
+///     |c| toml::from_str(c),
+///     |c: &Config| {
+///         if c.port < 1024 {
+///             return Err(serde::de::Error::custom("port must be at least 1024"));
+///         }
+///         Ok(())
+///     },
+/// )
+/// .expect("Could not load configuration");
+/// ```
+pub fn load_validated<P, T, E, D, V>(path: P, deserializer: D, validate: V) -> Result<T, E>
+where
+    P: AsRef<Path>,
+    D: FnOnce(&str) -> std::result::Result<T, E>,
+    V: FnOnce(&T) -> std::result::Result<(), E>,
+{
+    let config = load_from_path(path, deserializer)?;
+    validate(&config).map_err(ConfigurationError::deserialize)?;
+
+    Ok(config)
+}
+
+/// Load a configuration from the file at the given path into an existing value.
+///
+/// The deserializer receives the content of the file along with the value to update, and may e.g.
+/// use `serde`'s `Deserialize::deserialize_in_place` or assign the fields which are backed by the
+/// file. This allows state which is not part of the file to be kept when reloading a
+/// configuration. If the deserializer fails, the value may have been partially updated.
+///
+/// # Examples
+///
+/// ```no_run
+/// use serde::Deserialize;
+///
+/// #[derive(Deserialize)]
+/// struct Config {
+///     message: String,
+///     #[serde(skip)]
+///     reloads: usize,
+/// }
+///
+/// let mut config = Config { message: String::new(), reloads: 0 };
+///
+/// graze::load_into("Config.toml", &mut config, |c, config: &mut Config| {
+///     config.message = toml::from_str::<Config>(c)?.message;
+///     config.reloads += 1;
+///     Ok::<_, toml::de::Error>(())
+/// })
+/// .expect("Could not load configuration");
+/// ```
+pub fn load_into<P, T, E, D>(path: P, target: &mut T, deserializer: D) -> Result<(), E>
+where
+    P: AsRef<Path>,
+    D: FnOnce(&str, &mut T) -> std::result::Result<(), E>,
+{
+    let content = read_to_string(path.as_ref())?;
+
+    deserializer(&content, target).map_err(ConfigurationError::deserialize)
+}
+
+/// Load a configuration from the file at the given path, migrating its content first.
+///
+/// The migrate closure receives the raw content of the file and returns the content to
+/// deserialize, e.g. rewriting a configuration written for an older version of the program. The
+/// file itself is left untouched; use [load_with_migration_persisted] to also write the migrated
+/// content back. An error returned by the migrate closure is propagated through
+/// [ConfigurationError::Deserialize].
+///
+/// # Examples
+///
+/// ```no_run
+/// use serde::Deserialize;
+///
+/// #[derive(Deserialize)]
+/// struct Config {
+///     message: String
+/// }
+///
+/// let config: Config = graze::load_with_migration(
+///     "Config.toml",
+///     |c| toml::from_str(c),
+///     |c| Ok(c.replace("greeting =", "message =")),
+/// )
+/// .expect("Could not load configuration");
+/// ```
+pub fn load_with_migration<P, T, E, D, Mi>(path: P, deserializer: D, migrate: Mi) -> Result<T, E>
+where
+    P: AsRef<Path>,
+    D: FnOnce(&str) -> std::result::Result<T, E>,
+    Mi: FnOnce(&str) -> std::result::Result<String, E>,
+{
+    let content = read_to_string(path.as_ref())?;
+    let migrated = migrate(&content).map_err(ConfigurationError::deserialize)?;
+
+    load_from_str(&migrated, deserializer)
+}
+
+/// Load a configuration from the file at the given path, migrating its content first and writing
+/// the migrated content back to the file.
+///
+/// This behaves like [load_with_migration], except that if the migrated content differs from the
+/// content of the file and deserializes successfully, it is written atomically to the file, as
+/// with [save_to_path].
+pub fn load_with_migration_persisted<P, T, E, D, Mi>(
+    path: P,
+    deserializer: D,
+    migrate: Mi,
+) -> Result<T, E>
+where
+    P: AsRef<Path>,
+    D: FnOnce(&str) -> std::result::Result<T, E>,
+    Mi: FnOnce(&str) -> std::result::Result<String, E>,
+{
+    let path = path.as_ref();
+    let content = read_to_string(path)?;
+    let migrated = migrate(&content).map_err(ConfigurationError::deserialize)?;
+    let config = load_from_str(&migrated, deserializer)?;
+
+    if migrated != content {
+        write_atomic(path, migrated.as_bytes()).map_err(|err| ConfigurationError::io(path, err))?;
+    }
+    Ok(config)
+}
+
+/// Load a partial configuration from the file at the given path and merge it over the default
+/// value.
+///
+/// The file is deserialized into a partial representation `U` of the configuration, typically with
+/// every field optional, which is combined with the default value by calling
+/// `merge(default, partial)`. This fills the fields missing from the file with their default
+/// values, without marking each field of the configuration with a default. If the file does not
+/// exist, the default value is used as is.
+///
+/// # Examples
+///
+/// ```no_run
+/// use serde::Deserialize;
+///
+/// #[derive(Default)]
+/// struct Config {
+///     message: String,
+///     volume: u8,
+/// }
+///
+/// #[derive(Deserialize)]
+/// struct PartialConfig {
+///     message: Option<String>,
+///     volume: Option<u8>,
+/// }
+///
+/// let config = graze::load_or_partial(
+///     "Config.toml",
+///     |c| toml::from_str::<PartialConfig>(c),
+///     Config::default,
+///     |default, partial| Config {
+///         message: partial.message.unwrap_or(default.message),
+///         volume: partial.volume.unwrap_or(default.volume),
+///     },
+/// )
+/// .expect("Could not load configuration");
+/// ```
+pub fn load_or_partial<P, T, U, E, D, F, M>(
+    path: P,
+    deserializer: D,
+    default: F,
+    merge: M,
+) -> Result<T, E>
+where
+    P: AsRef<Path>,
+    D: FnOnce(&str) -> std::result::Result<U, E>,
+    F: FnOnce() -> T,
+    M: FnOnce(T, U) -> T,
+{
+    match read_if_exists(path.as_ref())? {
+        Some(content) => Ok(merge(default(), load_from_str(&content, deserializer)?)),
+        None => Ok(default()),
+    }
+}
+
+/// Load a base configuration and merge an optional overlay configuration over it.
+///
+/// The base file must exist. If the overlay file exists, it is loaded with the same deserializer
+/// and combined with the base by calling `merge(base, overlay)`, where the overlay is expected to
+/// take precedence. If the overlay file does not exist, the base configuration is returned as is.
+/// Any other failure to read the overlay file, e.g. because it is unreadable, is returned as an
+/// error.
+///
+/// # Examples
+///
+/// ```no_run
+/// use serde::Deserialize;
+///
+/// #[derive(Deserialize)]
+/// struct Config {
+///     message: String
+/// }
+///
+/// let config: Config = graze::load_merged(
+///     "Config.toml",
+///     "Config.local.toml",
+///     |c| toml::from_str(c),
+///     |_base, overlay| overlay,
+/// )
+/// .expect("Could not load configuration");
+/// ```
+pub fn load_merged<P, T, E, D, M>(base: P, overlay: P, deserializer: D, merge: M) -> Result<T, E>
+where
+    P: AsRef<Path>,
+    D: Fn(&str) -> std::result::Result<T, E>,
+    M: FnOnce(T, T) -> T,
+{
+    let base = load_from_path(base, &deserializer)?;
+
+    match read_if_exists(overlay.as_ref())? {
+        Some(content) => Ok(merge(base, load_from_str(&content, &deserializer)?)),
+        None => Ok(base),
+    }
+}
+
+/// Load a configuration from the file at the given path, or use the default value if the file does
+/// not exist.
+///
+/// Only a file which does not exist is replaced by the default value. Any other failure to read the
+/// file, e.g. because it is unreadable or a directory, is returned as an error.
+///
+/// # Examples
+///
+/// ```
+/// use serde::Deserialize;
+///
+/// #[derive(Deserialize, Default)]
+/// struct Config {
+///     message: String
+/// }
+///
+/// let config = graze::load_or_default("Config.toml", |s| toml::from_str(s), Config::default);
+/// ```
+pub fn load_or_default<P, T, E, D, F>(path: P, deserializer: D, default: F) -> Result<T, E>
+where
+    P: AsRef<Path>,
+    D: FnOnce(&str) -> std::result::Result<T, E>,
+    F: FnOnce() -> T,
+{
+    load_with_policy(path, deserializer, MissingPolicy::default(default))
+}
+
+/// Load a configuration from the file at the given path, or use the default value if the file does
+/// not exist, where computing the default value may fail.
+///
+/// This behaves like [load_or_default], except that the default function returns a [Result],
+/// e.g. because the default value is read from an environment variable. An error returned by the
+/// default function is propagated through [ConfigurationError::Default].
+///
+/// # Examples
+///
+/// ```no_run
+/// use serde::Deserialize;
+///
+/// #[derive(Deserialize)]
+/// struct Config {
+///     message: String
+/// }
+///
+/// let config = graze::load_or_try_default(
+///     "Config.toml",
+///     |s| toml::from_str(s).map_err(|e| e.to_string()),
+///     || {
+///         let message = std::env::var("MESSAGE").map_err(|e| e.to_string())?;
+///         Ok(Config { message })
+///     },
+/// )
+/// .expect("Could not load configuration");
+/// ```
+pub fn load_or_try_default<P, T, E, D, F>(path: P, deserializer: D, default: F) -> Result<T, E>
+where
+    P: AsRef<Path>,
+    D: FnOnce(&str) -> std::result::Result<T, E>,
+    F: FnOnce() -> std::result::Result<T, E>,
+{
+    match read_if_exists(path.as_ref())? {
+        Some(content) => load_from_str(&content, deserializer),
+        None => default().map_err(ConfigurationError::Default),
+    }
+}
+
+/// Load a configuration from the file at the given path, or use the given value if the file does
+/// not exist.
+///
+/// This behaves like [load_or_default], but takes the default value directly rather than a function
+/// producing it. The value is dropped if the file exists.
+///
+/// # Examples
+///
+/// ```
+/// use serde::Deserialize;
+///
+/// #[derive(Deserialize)]
+/// struct Config {
+///     message: String
+/// }
+///
+/// let default = Config { message: "Hello, world!".to_string() };
+///
+/// let config = graze::load_or_value("Config.toml", |s| toml::from_str(s), default);
+/// ```
+pub fn load_or_value<P, T, E, D>(path: P, deserializer: D, default: T) -> Result<T, E>
+where
+    P: AsRef<Path>,
+    D: FnOnce(&str) -> std::result::Result<T, E>,
+{
+    load_or_default(path, deserializer, || default)
+}
+
+/// Load a configuration from the file at the given path, or from the given embedded content if the
+/// file does not exist.
+///
+/// The embedded content, typically included in the program with [include_str], is deserialized with
+/// the same deserializer as the file, so no [Default] implementation is needed, and mistakes in the
+/// embedded content are reported as [ConfigurationError::Deserialize] rather than going unnoticed.
+///
+/// # Examples
+///
+/// ```no_run
+/// use serde::Deserialize;
+///
+/// #[derive(Deserialize)]
+/// struct Config {
+///     message: String
+/// }
+///
+/// let config: Config = graze::load_or_embedded(
+///     "Config.toml",
+///     |s| toml::from_str(s),
+///     "message = 'Hello, world!'", // e.g. include_str!("Default.toml")
+/// )
+/// .expect("Could not load configuration");
+/// ```
+pub fn load_or_embedded<P, T, E, D>(
+    path: P,
+    deserializer: D,
+    embedded: &'static str,
+) -> Result<T, E>
+where
+    P: AsRef<Path>,
+    D: FnOnce(&str) -> std::result::Result<T, E>,
+{
+    match read_if_exists(path.as_ref())? {
+        Some(content) => load_from_str(&content, deserializer),
+        None => load_from_str(embedded, deserializer),
+    }
+}
+
+/// Load a configuration from the file at the given path, or use the default value if the file does
+/// not exist or cannot be loaded for any other reason.
+///
+/// Unlike [load_or_default], which only replaces a missing file, this never fails: a file which is
+/// unreadable or incorrect is also replaced by the default value, so that e.g. a corrupted
+/// configuration file does not prevent the program from starting. The error is passed to `on_error`
+/// first, e.g. to log it; `on_error` is not called when the file does not exist.
+///
+/// # Examples
+///
+/// ```
+/// use serde::Deserialize;
+///
+/// #[derive(Deserialize, Default)]
+/// struct Config {
+///     message: String
+/// }
+///
+/// let config = graze::load_or_default_on_error(
+///     "Config.toml",
+///     |s| toml::from_str(s),
+///     Config::default,
+///     |err| eprintln!("Using the default configuration: {err}"),
+/// );
+/// ```
+pub fn load_or_default_on_error<P, T, E, D, F, O>(
+    path: P,
+    deserializer: D,
+    default: F,
+    on_error: O,
+) -> T
+where
+    P: AsRef<Path>,
+    D: FnOnce(&str) -> std::result::Result<T, E>,
+    F: FnOnce() -> T,
+    O: FnOnce(&ConfigurationError<E>),
+{
+    match load_from_path(path, deserializer) {
+        Ok(config) => config,
+        Err(ConfigurationError::NotFound { .. }) => default(),
+        Err(err) => {
+            on_error(&err);
+            default()
+        }
+    }
+}
+
+/// Load a configuration from the file at the given path and validate it, or use the default value
+/// if the file does not exist.
+///
+/// The validator only runs on a configuration loaded from the file, never on the default value. See
+/// [load_validated] for details.
+pub fn load_or_default_validated<P, T, E, D, F, V>(
+    path: P,
+    deserializer: D,
+    default: F,
+    validate: V,
+) -> Result<T, E>
+where
+    P: AsRef<Path>,
+    D: FnOnce(&str) -> std::result::Result<T, E>,
+    F: FnOnce() -> T,
+    V: FnOnce(&T) -> std::result::Result<(), E>,
+{
+    match load_or_default_verbose(path, deserializer, default)? {
+        Loaded::FromFile(config) => {
+            validate(&config).map_err(ConfigurationError::deserialize)?;
+            Ok(config)
+        }
+        Loaded::Default(config) => Ok(config),
+    }
+}
+
+/// Load a configuration from the file at the given path, or use the default value if the file does
+/// not exist.
+///
+/// If the file does not exist, the default value will be written to the file at the given path.
+/// Any missing parent directories of the path are created first. Any other failure to read the
+/// file, e.g. because it is unreadable or a directory, is returned as an error.
+///
+/// An error returned by the serializer is propagated through [ConfigurationError::Serialize],
+/// so the serializer must return the same error type as the deserializer. With formats whose
+/// serializer and deserializer errors differ, such as `toml`, the serializer error can be converted
+/// using `serde::de::Error::custom`, as in the example below.
+///
+/// The default is written atomically: the serialized data is first written to a temporary sibling
+/// file (e.g. `Config.toml.tmp-<pid>`), which is then renamed over the target path. The temporary
+/// file is always newly created, so an existing file or symbolic link at its name is never reused;
+/// another name is tried instead. A crash during the write can therefore never leave a truncated
+/// configuration file behind. The rename is only atomic when the temporary file and the target
+/// reside on the same filesystem, which holds unless the target path is itself a mount point.
+///
+/// An existing file is never replaced: if another process creates the file while the default is
+/// being written, the file it created is loaded instead.
+///
+/// With the `gzip` feature, the default is compressed if the path has a `.gz` extension, as is done
+/// by every function writing a configuration file.
+///
+/// # Examples
+///
+/// ```no_run
+/// use serde::{Serialize, Deserialize};
+///
+/// #[derive(Serialize, Deserialize)]
+/// struct Config {
+///     message: String
+/// }
+///
+/// impl Default for Config {
+///     fn default() -> Self {
+///         Self { message: "Hello, world!".to_string() }
+///     }
+/// }
+///
+/// // If Config.toml exists, deserialize the content using toml::from_str.
+/// //
+/// // If the file does not exist, use the value returned from Config::default(), serialize the
+/// // value, and save the serialized data to Config.toml.
+/// let config = graze::load_or_write_default("Config.toml",
+///     |s| toml::from_str(s),
+///     |c| toml::to_string(c).map_err(serde::de::Error::custom),
+///     Config::default
+/// );
+/// ```
+pub fn load_or_write_default<P, T, E, D, S, F, B>(
+    path: P,
+    deserializer: D,
+    serializer: S,
+    default: F,
+) -> Result<T, E>
+where
+    P: AsRef<Path>,
+    D: FnOnce(&str) -> std::result::Result<T, E>,
+    S: FnOnce(&T) -> std::result::Result<B, E>,
+    B: AsRef<[u8]>,
+    F: FnOnce() -> T,
+{
+    load_with_policy(
+        path,
+        deserializer,
+        MissingPolicy::write_default(serializer, default),
+    )
+}
+
+/// Load a configuration from the file at the given path, or use the default value if the file does
+/// not exist, durably writing the default value to the file.
+///
+/// This behaves like [load_or_write_default], except that the written default is flushed to disk
+/// before it becomes visible at `path`, and the directory containing the file is flushed afterwards
+/// on Unix. The default therefore survives a crash or power loss shortly after this function
+/// returns, at the cost of a slower write.
+///
+/// # Examples
+///
+/// ```no_run
+/// use serde::{Serialize, Deserialize};
+///
+/// #[derive(Serialize, Deserialize, Default)]
+/// struct Config {
+///     message: String
+/// }
+///
+/// let config = graze::load_or_write_default_durable(
+///     "Config.toml",
+///     |s| toml::from_str(s),
+///     |c| toml::to_string(c).map_err(serde::de::Error::custom),
+///     Config::default,
+/// )
+/// .expect("Could not load configuration");
+/// ```
+pub fn load_or_write_default_durable<P, T, E, D, S, F, B>(
+    path: P,
+    deserializer: D,
+    serializer: S,
+    default: F,
+) -> Result<T, E>
+where
+    P: AsRef<Path>,
+    D: FnOnce(&str) -> std::result::Result<T, E>,
+    S: FnOnce(&T) -> std::result::Result<B, E>,
+    B: AsRef<[u8]>,
+    F: FnOnce() -> T,
+{
+    try_load_or_write_with(
+        path.as_ref(),
+        deserializer,
+        serializer,
+        default,
+        write_new_durable,
+    )
+    .map(|(data, _)| data)
+}
+
+/// Load a configuration from the file at the given path, or write a template and use the default
+/// value if the file does not exist.
+///
+/// This behaves like [load_or_write_default], except that the given template is written instead of
+/// the serialized default value, so the configuration type does not need to be serializable. The
+/// template is written as-is and is not deserialized, so it should describe the same configuration
+/// as the default value.
+///
+/// # Examples
+///
+/// ```no_run
+/// use serde::Deserialize;
+///
+/// #[derive(Deserialize, Default)]
+/// struct Config {
+///     message: String
+/// }
+///
+/// let config: Config = graze::load_or_write_template(
+///     "Config.toml",
+///     |s| toml::from_str(s),
+///     "message = ''\n",
+///     Config::default,
+/// )
+/// .expect("Could not load configuration");
+/// ```
+pub fn load_or_write_template<P, T, E, D, F>(
+    path: P,
+    deserializer: D,
+    template: &str,
+    default: F,
+) -> Result<T, E>
+where
+    P: AsRef<Path>,
+    D: FnOnce(&str) -> std::result::Result<T, E>,
+    F: FnOnce() -> T,
+{
+    try_load_or_write_with(
+        path.as_ref(),
+        deserializer,
+        |_| Ok(template),
+        default,
+        write_new_atomic,
+    )
+    .map(|(data, _)| data)
+}
+
+/// Write a template to the file at the given path if the file does not exist.
+///
+/// The template is written byte-for-byte, so comments and formatting which a serializer would
+/// discard are preserved. As with [load_or_write_default], any missing parent directories are
+/// created first and the file is written atomically. An existing file is never replaced.
+///
+/// Returns `true` if the template was written, or `false` if the file already existed.
+///
+/// # Examples
+///
+/// ```no_run
+/// let template = "# The message printed on startup\nmessage = 'Hello, world!'\n";
+///
+/// if graze::write_template("Config.toml", template).expect("Could not write template") {
+///     println!("Wrote a new configuration file");
+/// }
+/// ```
+pub fn write_template<P>(path: P, template: &str) -> io::Result<bool>
+where
+    P: AsRef<Path>,
+{
+    let path = path.as_ref();
+    create_parent_dirs(path)?;
+
+    match write_new_atomic(path, template.as_bytes()) {
+        Ok(()) => Ok(true),
+        Err(err) if err.kind() == io::ErrorKind::AlreadyExists => Ok(false),
+        Err(err) => Err(err),
+    }
+}
+
+/// Check that a configuration file could be written to the given path.
+///
+/// The parent directory of `path` must exist and be writable, which is checked by creating and
+/// removing a new temporary sibling of `path`, in the same way as [save_to_path] does. The file at
+/// `path` itself is neither read nor modified. This is useful for failing early, e.g. at startup,
+/// rather than only discovering that a configuration cannot be saved once [save_to_path] is
+/// called.
+///
+/// # Examples
+///
+/// ```no_run
+/// graze::verify_writable("Config.toml").expect("The configuration directory is not writable");
+/// ```
+pub fn verify_writable<P>(path: P) -> io::Result<()>
+where
+    P: AsRef<Path>,
+{
+    let (_, tmp) = create_temp(path.as_ref(), &OpenOptions::new())?;
+    fs::remove_file(&tmp)
+}
+
+/// Load a configuration from the raw bytes of the file at the given path, or use the default value
+/// if the file does not exist, writing the default value to the file.
+///
+/// This is the binary counterpart of [load_or_write_default], in the same way as
+/// [load_from_path_bytes] is for [load_from_path]. See [load_or_write_default] for details.
+///
+/// # Examples
+///
+/// ```no_run
+/// let config: Vec<u8> = graze::load_or_write_default_bytes(
+///     "Config.bin",
+///     |c| Ok::<_, std::convert::Infallible>(c.to_vec()),
+///     |c| Ok(c.clone()),
+///     || vec![1, 2, 3],
+/// )
+/// .expect("Could not load configuration");
+/// ```
+pub fn load_or_write_default_bytes<P, T, E, D, S, F, B>(
+    path: P,
+    deserializer: D,
+    serializer: S,
+    default: F,
+) -> Result<T, E>
+where
+    P: AsRef<Path>,
+    D: FnOnce(&[u8]) -> std::result::Result<T, E>,
+    S: FnOnce(&T) -> std::result::Result<B, E>,
+    B: AsRef<[u8]>,
+    F: FnOnce() -> T,
+{
+    try_load_or_write_bytes_with(
+        path.as_ref(),
+        deserializer,
+        serializer,
+        default,
+        write_new_atomic,
+    )
+    .map(|(data, _)| data)
+}
+
+/// Save a configuration to the file at the given path, replacing the file if it already exists.
+///
+/// The file is written atomically, in the same way as [load_or_write_default]. If the file already
+/// exists, its permissions are kept, e.g. a file written with
+/// [load_or_write_default_with_mode] stays readable by its owner only. An error returned by the
+/// serializer is propagated through [ConfigurationError::Serialize].
+///
+/// # Examples
+///
+/// ```no_run
+/// use serde::Serialize;
+///
+/// #[derive(Serialize)]
+/// struct Config {
+///     message: String
+/// }
+///
+/// let config = Config { message: "Hello, world!".to_string() };
+///
+/// graze::save_to_path("Config.toml", &config, |c| toml::to_string(c))
+///     .expect("Could not save configuration");
+/// ```
+pub fn save_to_path<P, T, E, S, B>(path: P, value: &T, serializer: S) -> Result<(), E>
+where
+    P: AsRef<Path>,
+    S: FnOnce(&T) -> std::result::Result<B, E>,
+    B: AsRef<[u8]>,
+{
+    let path = path.as_ref();
+    let data = serializer(value).map_err(ConfigurationError::serialize)?;
+    write_atomic(path, data.as_ref()).map_err(|err| ConfigurationError::io(path, err))?;
+
+    Ok(())
+}
+
+/// Write the default value to the file at the given path, replacing the file if it already exists,
+/// and return the default value.
+///
+/// This is the counterpart of [load_or_write_default] for resetting a configuration to its
+/// defaults: the default value is always written, regardless of whether the file exists or what it
+/// contains. Any missing parent directories are created first, and the file is written atomically,
+/// as with [save_to_path].
+///
+/// # Examples
+///
+/// ```no_run
+/// use serde::Serialize;
+///
+/// #[derive(Serialize, Default)]
+/// struct Config {
+///     message: String
+/// }
+///
+/// let config = graze::write_default("Config.toml", |c| toml::to_string(c), Config::default)
+///     .expect("Could not reset configuration");
+/// ```
+pub fn write_default<P, T, E, S, F, B>(path: P, serializer: S, default: F) -> Result<T, E>
+where
+    P: AsRef<Path>,
+    S: FnOnce(&T) -> std::result::Result<B, E>,
+    B: AsRef<[u8]>,
+    F: FnOnce() -> T,
+{
+    let path = path.as_ref();
+    let data = default();
+    let serialized = serializer(&data).map_err(ConfigurationError::serialize)?;
+
+    create_parent_dirs(path)
+        .and_then(|_| write_atomic(path, serialized.as_ref()))
+        .map_err(|err| ConfigurationError::io(path, err))?;
+
+    Ok(data)
+}
+
+/// The suffix appended to the file name of backups made by [save_to_path_with_backup], if no other
+/// suffix is desired.
+pub const DEFAULT_BACKUP_SUFFIX: &str = ".bak";
+
+/// Save a configuration to the file at the given path, first backing up the existing file.
+///
+/// If the file already exists, it is copied to a sibling file whose name has `suffix` appended
+/// (e.g. `Config.toml.bak` with [DEFAULT_BACKUP_SUFFIX]), replacing any previous backup. The new
+/// content is then written atomically, as with [save_to_path]. If writing the new content fails,
+/// the backup is left intact. If the file does not exist, no backup is made.
+///
+/// # Examples
+///
+/// ```no_run
+/// use serde::Serialize;
+///
+/// #[derive(Serialize)]
+/// struct Config {
+///     message: String
+/// }
+///
+/// let config = Config { message: "Hello, world!".to_string() };
+///
+/// graze::save_to_path_with_backup(
+///     "Config.toml",
+///     &config,
+///     |c| toml::to_string(c),
+///     graze::DEFAULT_BACKUP_SUFFIX,
+/// )
+/// .expect("Could not save configuration");
+/// ```
+pub fn save_to_path_with_backup<P, T, E, S, B>(
+    path: P,
+    value: &T,
+    serializer: S,
+    suffix: &str,
+) -> Result<(), E>
+where
+    P: AsRef<Path>,
+    S: FnOnce(&T) -> std::result::Result<B, E>,
+    B: AsRef<[u8]>,
+{
+    let path = path.as_ref();
+    let data = serializer(value).map_err(ConfigurationError::serialize)?;
+
+    if path.exists() {
+        let backup = sibling_with_suffix(path, suffix);
+        fs::copy(path, &backup).map_err(|err| ConfigurationError::io(&backup, err))?;
+    }
+    write_atomic(path, data.as_ref()).map_err(|err| ConfigurationError::io(path, err))?;
+
+    Ok(())
+}
+
+/// Save a configuration to the file at the given path, unless the file already has the same
+/// content.
+///
+/// The serialized configuration is compared to the current content of the file, and is only
+/// written, as with [save_to_path], if the two differ or the file does not exist. This avoids
+/// needlessly updating the modification time of the file. Returns whether the file was written.
+///
+/// # Examples
+///
+/// ```no_run
+/// use serde::Serialize;
+///
+/// #[derive(Serialize)]
+/// struct Config {
+///     message: String
+/// }
+///
+/// let config = Config { message: "Hello, world!".to_string() };
+///
+/// let written = graze::save_if_changed("Config.toml", &config, |c| toml::to_string(c))
+///     .expect("Could not save configuration");
+///
+/// if !written {
+///     println!("The configuration was already up to date");
+/// }
+/// ```
+pub fn save_if_changed<P, T, E, S, B>(path: P, value: &T, serializer: S) -> Result<bool, E>
+where
+    P: AsRef<Path>,
+    S: FnOnce(&T) -> std::result::Result<B, E>,
+    B: AsRef<[u8]>,
+{
+    let path = path.as_ref();
+    let data = serializer(value).map_err(ConfigurationError::serialize)?;
+
+    if read_bytes_if_exists(path)?.is_some_and(|content| content == data.as_ref()) {
+        return Ok(false);
+    }
+    write_atomic(path, data.as_ref()).map_err(|err| ConfigurationError::io(path, err))?;
+
+    Ok(true)
+}
+
+/// Load a configuration from the file at `path`, or write and use the default value if the file
+/// does not exist.
+///
+/// An error returned by the serializer is propagated through [ConfigurationError::Serialize].
+/// The serialized default is written using the given function, after creating any missing parent
+/// directories of `path`. The function must fail with [io::ErrorKind::AlreadyExists] rather than
+/// replace a file created at `path` in the meantime, in which case that file is loaded instead.
+fn try_load_or_write_with<T, E, D, S, F, B, W>(
+    path: &Path,
+    deserializer: D,
+    serializer: S,
+    default: F,
+    write: W,
+) -> Result<(T, LoadMeta), E>
+where
+    D: FnOnce(&str) -> std::result::Result<T, E>,
+    S: FnOnce(&T) -> std::result::Result<B, E>,
+    B: AsRef<[u8]>,
+    F: FnOnce() -> T,
+    W: FnOnce(&Path, &[u8]) -> io::Result<()>,
+{
+    try_load_or_write_content_with(
+        path,
+        read_if_exists,
+        |content: &String| deserializer(content),
+        serializer,
+        default,
+        write,
+    )
+}
+
+/// Load a configuration from the raw bytes of the file at `path`, or write and use the default
+/// value if the file does not exist.
+///
+/// See [try_load_or_write_with].
+fn try_load_or_write_bytes_with<T, E, D, S, F, B, W>(
+    path: &Path,
+    deserializer: D,
+    serializer: S,
+    default: F,
+    write: W,
+) -> Result<(T, LoadMeta), E>
+where
+    D: FnOnce(&[u8]) -> std::result::Result<T, E>,
+    S: FnOnce(&T) -> std::result::Result<B, E>,
+    B: AsRef<[u8]>,
+    F: FnOnce() -> T,
+    W: FnOnce(&Path, &[u8]) -> io::Result<()>,
+{
+    try_load_or_write_content_with(
+        path,
+        read_bytes_if_exists,
+        |content: &Vec<u8>| deserializer(content),
+        serializer,
+        default,
+        write,
+    )
+}
+
+/// Load a configuration from the file at `path`, or write and use the default value if the file
+/// does not exist, reading the file using the given function.
+///
+/// `read` must return [None] if the file does not exist. See [try_load_or_write_with].
+fn try_load_or_write_content_with<C, T, E, R, D, S, F, B, W>(
+    path: &Path,
+    read: R,
+    deserializer: D,
+    serializer: S,
+    default: F,
+    write: W,
+) -> Result<(T, LoadMeta), E>
+where
+    R: Fn(&Path) -> Result<Option<C>, E>,
+    D: FnOnce(&C) -> std::result::Result<T, E>,
+    S: FnOnce(&T) -> std::result::Result<B, E>,
+    B: AsRef<[u8]>,
+    F: FnOnce() -> T,
+    W: FnOnce(&Path, &[u8]) -> io::Result<()>,
+{
+    let meta = |wrote_default| LoadMeta {
+        path: path.to_path_buf(),
+        wrote_default,
+    };
+
+    trace!("Reading configuration file '{}'", path.display());
+
+    if let Some(content) = read(path)? {
+        let config = deserializer(&content).map_err(ConfigurationError::deserialize)?;
+        return Ok((config, meta(false)));
+    }
+
+    debug!(
+        "Configuration file '{}' does not exist, writing default",
+        path.display()
+    );
+
+    let data = default();
+    let serialized = serializer(&data).map_err(ConfigurationError::serialize)?;
+    create_parent_dirs(path).map_err(|err| ConfigurationError::io(path, err))?;
+
+    match write(path, serialized.as_ref()) {
+        Err(err) if err.kind() == io::ErrorKind::AlreadyExists => {
+            debug!(
+                "Configuration file '{}' was created concurrently, loading it instead",
+                path.display()
+            );
+            let content = read(path)?.ok_or_else(|| ConfigurationError::NotFound {
+                paths: vec![path.to_path_buf()],
+            })?;
+            let config = deserializer(&content).map_err(ConfigurationError::deserialize)?;
+            return Ok((config, meta(false)));
+        }
+        result => result.map_err(|err| ConfigurationError::io(path, err))?,
+    }
+
+    debug!("Wrote default configuration to '{}'", path.display());
+    Ok((data, meta(true)))
+}
+
+/// Read the file at `path`, reporting a missing file as [ConfigurationError::NotFound].
+///
+/// With the `gzip` feature, a gzip compressed file is decompressed.
+fn read_to_string<E>(path: &Path) -> Result<String, E> {
+    #[cfg(feature = "gzip")]
+    return String::from_utf8(read_bytes(path)?).map_err(|err| {
+        ConfigurationError::io(path, io::Error::new(io::ErrorKind::InvalidData, err))
+    });
+
+    #[cfg(not(feature = "gzip"))]
+    {
+        reject_directory(path)?;
+        fs::read_to_string(path).map_err(|err| read_error(path, err))
+    }
+}
+
+/// Read the file at `path`, reporting a file larger than `limit` bytes as
+/// [ConfigurationError::TooLarge] without reading all of it.
+///
+/// The size of the file is checked before it is read, and is used as the capacity of the buffer.
+/// With the `gzip` feature, a gzip compressed file is decompressed; the limit applies to the
+/// compressed size.
+fn read_to_string_bounded<E>(path: &Path, limit: u64) -> Result<String, E> {
+    let too_large = |size| ConfigurationError::TooLarge {
+        path: path.to_path_buf(),
+        size,
+        limit,
+    };
+
+    let (file, metadata) = open_with_metadata(path)?;
+    let size = metadata.len();
+
+    if size > limit {
+        return Err(too_large(size));
+    }
+
+    let mut content = Vec::with_capacity(size as usize);
+    file.take(limit + 1)
+        .read_to_end(&mut content)
+        .map_err(|err| ConfigurationError::io(path, err))?;
+
+    if content.len() as u64 > limit {
+        return Err(too_large(content.len() as u64));
+    }
+
+    decode_content(path, content)
+}
+
+/// Read the file at `path` in chunks, calling `on_progress` with the number of bytes read so far
+/// and the size of the file after each chunk.
+///
+/// With the `gzip` feature, a gzip compressed file is decompressed; the progress is reported in
+/// compressed bytes.
+fn read_to_string_progress<E, F>(path: &Path, mut on_progress: F) -> Result<String, E>
+where
+    F: FnMut(u64, u64),
+{
+    let (file, metadata) = open_with_metadata(path)?;
+    let total = metadata.len();
+    let mut reader = BufReader::new(file);
+    let mut content = Vec::with_capacity(total as usize);
+
+    loop {
+        let chunk = reader
+            .fill_buf()
+            .map_err(|err| ConfigurationError::io(path, err))?;
+
+        if chunk.is_empty() {
+            break;
+        }
+
+        let len = chunk.len();
+        content.extend_from_slice(chunk);
+        reader.consume(len);
+        on_progress(content.len() as u64, total);
+    }
+
+    decode_content(path, content)
+}
+
+/// Open the file at `path` for reading, along with its metadata.
+fn open_with_metadata<E>(path: &Path) -> Result<(fs::File, fs::Metadata), E> {
+    open_with_metadata_using(path, OpenOptions::new().read(true))
+}
+
+/// Open the file at `path` using the given options, along with its metadata.
+fn open_with_metadata_using<E>(
+    path: &Path,
+    options: &OpenOptions,
+) -> Result<(fs::File, fs::Metadata), E> {
+    let file = options.open(path).map_err(|err| read_error(path, err))?;
+    let metadata = file
+        .metadata()
+        .map_err(|err| ConfigurationError::io(path, err))?;
+
+    if metadata.is_dir() {
+        return Err(ConfigurationError::IsDirectory {
+            path: path.to_path_buf(),
+        });
+    }
+
+    Ok((file, metadata))
+}
+
+/// Convert the raw content read from the file at `path` into a string, reporting content which is
+/// not valid UTF-8 as [ConfigurationError::Io].
+///
+/// With the `gzip` feature, gzip compressed content is decompressed first.
+fn decode_content<E>(path: &Path, content: Vec<u8>) -> Result<String, E> {
+    #[cfg(feature = "gzip")]
+    let content = gzip::decompress(content).map_err(|err| ConfigurationError::io(path, err))?;
+
+    String::from_utf8(content).map_err(|err| {
+        ConfigurationError::io(path, io::Error::new(io::ErrorKind::InvalidData, err))
+    })
+}
+
+/// Read the file at `path`, returning [None] if it does not exist.
+///
+/// Only a read failing with [io::ErrorKind::NotFound] counts as the file not existing; any other
+/// error (e.g. the path being a directory or unreadable) is returned.
+fn read_if_exists<E>(path: &Path) -> Result<Option<String>, E> {
+    match read_to_string(path) {
+        Ok(content) => Ok(Some(content)),
+        Err(ConfigurationError::NotFound { .. }) => Ok(None),
+        Err(err) => Err(err),
+    }
+}
+
+/// Read the raw bytes of the file at `path`, reporting a missing file as
+/// [ConfigurationError::NotFound].
+///
+/// With the `gzip` feature, a gzip compressed file is decompressed.
+fn read_bytes<E>(path: &Path) -> Result<Vec<u8>, E> {
+    reject_directory(path)?;
+    let content = fs::read(path).map_err(|err| read_error(path, err))?;
+
+    #[cfg(feature = "gzip")]
+    let content = gzip::decompress(content).map_err(|err| ConfigurationError::io(path, err))?;
+
+    Ok(content)
+}
+
+/// Read the raw bytes of the file at `path`, returning [None] if it does not exist.
+///
+/// See [read_if_exists].
+fn read_bytes_if_exists<E>(path: &Path) -> Result<Option<Vec<u8>>, E> {
+    match read_bytes(path) {
+        Ok(content) => Ok(Some(content)),
+        Err(ConfigurationError::NotFound { .. }) => Ok(None),
+        Err(err) => Err(err),
+    }
+}
+
+/// Report `path` as [ConfigurationError::IsDirectory] if it is a directory.
+///
+/// This is checked before reading, as the error from reading a directory differs between platforms.
+fn reject_directory<E>(path: &Path) -> Result<(), E> {
+    match fs::metadata(path) {
+        Ok(metadata) if metadata.is_dir() => Err(ConfigurationError::IsDirectory {
+            path: path.to_path_buf(),
+        }),
+        _ => Ok(()),
+    }
+}
+
+/// Convert an error from reading the file at `path` into a [ConfigurationError].
+fn read_error<E>(path: &Path, err: io::Error) -> ConfigurationError<E> {
+    match err.kind() {
+        io::ErrorKind::NotFound => ConfigurationError::NotFound {
+            paths: vec![path.to_path_buf()],
+        },
+        io::ErrorKind::IsADirectory => ConfigurationError::IsDirectory {
+            path: path.to_path_buf(),
+        },
+        _ => ConfigurationError::io(path, err),
+    }
+}
+
+/// Create all missing parent directories of `path`.
+fn create_parent_dirs(path: &Path) -> io::Result<()> {
+    match path.parent() {
+        Some(parent) => fs::create_dir_all(parent),
+        None => Ok(()),
+    }
+}
+
+/// Write `contents` to a temporary sibling of `path`, then rename it over `path`.
+///
+/// The temporary file is removed if either the write or the rename fails.
+fn write_atomic(path: &Path, contents: &[u8]) -> io::Result<()> {
+    write_atomic_with(path, contents, &OpenOptions::new())
+}
+
+/// Write `contents` to a temporary sibling of `path` opened using the given options, then rename
+/// it over `path`.
+///
+/// See [write_atomic_secured].
+fn write_atomic_with(path: &Path, contents: &[u8], options: &OpenOptions) -> io::Result<()> {
+    write_atomic_secured(path, contents, options, |_| Ok(()))
+}
+
+/// Write `contents` to a temporary sibling of `path` opened using the given options, then rename
+/// it over `path`. `secure` is called with the path of the temporary file once it is created,
+/// before anything is written to it.
+///
+/// If `path` already exists, its permissions are copied to the temporary file before `secure` is
+/// called, so that replacing the file does not change its permissions. The temporary file is
+/// removed if either the write or the rename fails.
+fn write_atomic_secured<S>(
+    path: &Path,
+    contents: &[u8],
+    options: &OpenOptions,
+    secure: S,
+) -> io::Result<()>
+where
+    S: Fn(&Path) -> io::Result<()>,
+{
+    #[cfg(feature = "gzip")]
+    let contents = &*gzip::compress_for(path, contents)?;
+
+    let permissions = match fs::metadata(path) {
+        Ok(metadata) => Some(metadata.permissions()),
+        Err(err) if err.kind() == io::ErrorKind::NotFound => None,
+        Err(err) => return Err(err),
+    };
+    let secure = |tmp: &Path| {
+        if let Some(permissions) = &permissions {
+            fs::set_permissions(tmp, permissions.clone())?;
+        }
+        secure(tmp)
+    };
+
+    let tmp = write_temp(path, contents, options, secure)?;
+    let result = fs::rename(&tmp, path);
+
+    if result.is_err() {
+        let _ = fs::remove_file(&tmp);
+    }
+    result
+}
+
+/// Write `contents` to `path` unless it already exists, in which case an
+/// [io::ErrorKind::AlreadyExists] error is returned.
+///
+/// See [write_new_atomic_with].
+fn write_new_atomic(path: &Path, contents: &[u8]) -> io::Result<()> {
+    write_new_atomic_with(path, contents, &OpenOptions::new())
+}
+
+/// Write `contents` to `path` unless it already exists, in which case an
+/// [io::ErrorKind::AlreadyExists] error is returned. The file is opened using the given options.
+///
+/// See [write_new_secured].
+fn write_new_atomic_with(path: &Path, contents: &[u8], options: &OpenOptions) -> io::Result<()> {
+    write_new_secured(path, contents, options, |_| Ok(()))
+}
+
+/// Write `contents` to `path` unless it already exists, in which case an
+/// [io::ErrorKind::AlreadyExists] error is returned. The file is opened using the given options,
+/// and `secure` is called with the path of the file once it is created, before anything is written
+/// to it.
+///
+/// The content is written to a temporary sibling of `path`, which is then hard linked to `path`.
+/// Creating the link fails if `path` exists, so a file created by another process in the meantime
+/// is never replaced, and a partially written file is never visible at `path`. If the link cannot
+/// be created for another reason (e.g. the filesystem does not support hard links), the file is
+/// instead created at `path` with [OpenOptions::create_new] and written in place.
+///
+/// The temporary file is always removed.
+fn write_new_secured<S>(
+    path: &Path,
+    contents: &[u8],
+    options: &OpenOptions,
+    secure: S,
+) -> io::Result<()>
+where
+    S: Fn(&Path) -> io::Result<()>,
+{
+    #[cfg(feature = "gzip")]
+    let contents = &*gzip::compress_for(path, contents)?;
+
+    let result = write_temp(path, contents, options, &secure).and_then(|tmp| {
+        let result = fs::hard_link(&tmp, path);
+        let _ = fs::remove_file(&tmp);
+        result
+    });
+
+    match result {
+        Err(err) if err.kind() != io::ErrorKind::AlreadyExists => {
+            let mut file = options.clone().write(true).create_new(true).open(path)?;
+            secure(path)?;
+            file.write_all(contents)
+        }
+        result => result,
+    }
+}
+
+/// Write `contents` to `path` unless it already exists, in the same way as [write_new_atomic], but
+/// durably.
+///
+/// The content is flushed to disk before it becomes visible at `path`, and the parent directory is
+/// flushed afterwards on Unix, so that both the content of the file and its creation survive a
+/// crash.
+fn write_new_durable(path: &Path, contents: &[u8]) -> io::Result<()> {
+    #[cfg(feature = "gzip")]
+    let contents = &*gzip::compress_for(path, contents)?;
+
+    let result = create_temp(path, &OpenOptions::new()).and_then(|(file, tmp)| {
+        let result = write_synced(file, contents).and_then(|_| fs::hard_link(&tmp, path));
+        let _ = fs::remove_file(&tmp);
+        result
+    });
+
+    match result {
+        Err(err) if err.kind() != io::ErrorKind::AlreadyExists => {
+            let file = OpenOptions::new().write(true).create_new(true).open(path)?;
+            write_synced(file, contents)?
+        }
+        result => result?,
+    }
+    sync_parent_dir(&StdFs, path)
+}
+
+/// Write `contents` to the given file, and flush it to disk.
+fn write_synced(mut file: fs::File, contents: &[u8]) -> io::Result<()> {
+    file.write_all(contents)?;
+    file.sync_all()
+}
+
+/// Flush the directory containing `path` in the given filesystem, making the creation or renaming
+/// of `path` durable.
+fn sync_parent_dir<V>(vfs: &V, path: &Path) -> io::Result<()>
+where
+    V: ConfigFs + ?Sized,
+{
+    match path.parent() {
+        Some(parent) if parent.as_os_str().is_empty() => vfs.sync(Path::new(".")),
+        Some(parent) => vfs.sync(parent),
+        None => Ok(()),
+    }
+}
+
+/// Write `contents` to a new temporary sibling of `path`, opened using the given options, returning
+/// the path of the temporary file.
+///
+/// `secure` is called with the path of the temporary file once it is created, before anything is
+/// written to it. If this fails, the temporary file is removed. See [create_temp].
+fn write_temp<S>(
+    path: &Path,
+    contents: &[u8],
+    options: &OpenOptions,
+    secure: S,
+) -> io::Result<PathBuf>
+where
+    S: Fn(&Path) -> io::Result<()>,
+{
+    let (mut file, tmp) = create_temp(path, options)?;
+
+    match secure(&tmp).and_then(|_| file.write_all(contents)) {
+        Ok(()) => Ok(tmp),
+        Err(err) => {
+            let _ = fs::remove_file(&tmp);
+            Err(err)
+        }
+    }
+}
+
+/// Create a new temporary sibling of `path` for writing, opened using the given options, returning
+/// the file and its path.
+///
+/// The file is opened with [OpenOptions::create_new], so an existing file or symbolic link is never
+/// reused, e.g. one left behind by a crashed process or planted by another user. Its permissions
+/// are therefore always those given by `options`. If the name is taken, the next name given by
+/// [temp_sibling] is tried.
+fn create_temp(path: &Path, options: &OpenOptions) -> io::Result<(fs::File, PathBuf)> {
+    let mut attempt = 0;
+
+    loop {
+        let tmp = temp_sibling(path, attempt);
+
+        match options.clone().write(true).create_new(true).open(&tmp) {
+            Err(err) if err.kind() == io::ErrorKind::AlreadyExists && attempt < TEMP_ATTEMPTS => {
+                attempt += 1
+            }
+            result => return result.map(|file| (file, tmp)),
+        }
+    }
+}
+
+/// Get the path of the temporary file used when atomically writing to `path`, for the given
+/// attempt at creating it.
+fn temp_sibling(path: &Path, attempt: u32) -> PathBuf {
+    match attempt {
+        0 => sibling_with_suffix(path, &format!(".tmp-{}", process::id())),
+        _ => sibling_with_suffix(path, &format!(".tmp-{}-{attempt}", process::id())),
+    }
+}
+
+/// Get the path of the sibling of `path` whose file name has `suffix` appended to it.
+fn sibling_with_suffix(path: &Path, suffix: &str) -> PathBuf {
+    let mut name = path.file_name().map(OsString::from).unwrap_or_default();
+    name.push(suffix);
+    path.with_file_name(name)
 }
@@ -0,0 +1,145 @@
+//! Control over the line endings of saved configuration files.
+
+use std::path::Path;
+
+use crate::{load_or_write_default, save_to_path, Result};
+
+/// A line ending to use in saved configuration files.
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum LineEnding {
+    /// A line feed (`\n`), as used on Unix-like systems.
+    Lf,
+
+    /// A carriage return followed by a line feed (`\r\n`), as used on Windows.
+    Crlf,
+}
+
+impl LineEnding {
+    /// Replace every line ending in `content` with this line ending.
+    ///
+    /// Both `\n` and `\r\n` are recognized as line endings; a lone `\r` is left untouched.
+    pub fn apply(self, content: &[u8]) -> Vec<u8> {
+        let mut normalized = Vec::with_capacity(content.len());
+        let mut bytes = content.iter().peekable();
+
+        while let Some(&byte) = bytes.next() {
+            match byte {
+                b'\r' if bytes.peek() == Some(&&b'\n') => {}
+                b'\n' if self == Self::Crlf => normalized.extend_from_slice(b"\r\n"),
+                _ => normalized.push(byte),
+            }
+        }
+        normalized
+    }
+}
+
+/// Save a configuration to the file at the given path, using the given line ending.
+///
+/// This behaves like [save_to_path], except that every line ending in the serialized content is
+/// replaced with `line_ending` before it is written. This keeps a file's line endings stable when
+/// it is rewritten by a serializer which only produces `\n`.
+///
+/// # Examples
+///
+/// ```no_run
+/// use graze::LineEnding;
+/// use serde::Serialize;
+///
+/// #[derive(Serialize)]
+/// struct Config {
+///     message: String
+/// }
+///
+/// let config = Config { message: "Hello, world!".to_string() };
+///
+/// graze::save_to_path_with_line_endings(
+///     "Config.toml",
+///     &config,
+///     |c| toml::to_string(c),
+///     LineEnding::Crlf,
+/// )
+/// .expect("Could not save configuration");
+/// ```
+pub fn save_to_path_with_line_endings<P, T, E, S, B>(
+    path: P,
+    value: &T,
+    serializer: S,
+    line_ending: LineEnding,
+) -> Result<(), E>
+where
+    P: AsRef<Path>,
+    S: FnOnce(&T) -> std::result::Result<B, E>,
+    B: AsRef<[u8]>,
+{
+    save_to_path(path, value, |c| {
+        serializer(c).map(|content| line_ending.apply(content.as_ref()))
+    })
+}
+
+/// Load a configuration from the file at the given path, or use the default value if the file does
+/// not exist, writing the default value to the file so that it ends with exactly one newline.
+///
+/// Whether serialized content ends with a newline depends on the serializer, and many tools expect
+/// every line of a text file, including the last one, to end with one. A missing final newline is
+/// added, and several final newlines are collapsed into one; a final `\r\n` is kept as it is. See
+/// [load_or_write_default] for details.
+///
+/// # Examples
+///
+/// ```no_run
+/// use serde::{Serialize, Deserialize};
+///
+/// #[derive(Serialize, Deserialize, Default)]
+/// struct Config {
+///     message: String
+/// }
+///
+/// let config = graze::load_or_write_default_trailing_newline(
+///     "Config.toml",
+///     |s| toml::from_str(s),
+///     |c| toml::to_string(c).map_err(serde::de::Error::custom),
+///     Config::default,
+/// )
+/// .expect("Could not load configuration");
+/// ```
+pub fn load_or_write_default_trailing_newline<P, T, E, D, S, F, B>(
+    path: P,
+    deserializer: D,
+    serializer: S,
+    default: F,
+) -> Result<T, E>
+where
+    P: AsRef<Path>,
+    D: FnOnce(&str) -> std::result::Result<T, E>,
+    S: FnOnce(&T) -> std::result::Result<B, E>,
+    B: AsRef<[u8]>,
+    F: FnOnce() -> T,
+{
+    load_or_write_default(
+        path,
+        deserializer,
+        |c| serializer(c).map(|content| with_trailing_newline(content.as_ref())),
+        default,
+    )
+}
+
+/// Copy `content`, making it end with exactly one line ending.
+///
+/// The last line ending of `content` is kept if it is `\r\n`; otherwise `\n` is used.
+fn with_trailing_newline(content: &[u8]) -> Vec<u8> {
+    let end = content
+        .iter()
+        .rposition(|&byte| byte != b'\n' && byte != b'\r')
+        .map_or(0, |i| i + 1);
+
+    let line_ending: &[u8] = if content.ends_with(b"\r\n") {
+        b"\r\n"
+    } else {
+        b"\n"
+    };
+
+    let mut terminated = Vec::with_capacity(end + line_ending.len());
+    terminated.extend_from_slice(&content[..end]);
+    terminated.extend_from_slice(line_ending);
+    terminated
+}
@@ -0,0 +1,173 @@
+//! A builder for configuring how a configuration is loaded.
+
+use std::path::{Path, PathBuf};
+
+use crate::{
+    load_from_str, read_if_exists, read_to_string_bounded, try_load_or_write_with, write_atomic,
+    write_new_atomic, ConfigurationError, Result,
+};
+
+type Deserializer<T, E> = Box<dyn FnOnce(&str) -> std::result::Result<T, E>>;
+type Serializer<T, E> = Box<dyn FnOnce(&T) -> std::result::Result<Vec<u8>, E>>;
+
+/// A builder for loading a configuration.
+///
+/// The free functions of this crate cover the most common ways of loading a configuration; this
+/// builder allows their behaviors to be combined.
+///
+/// # Examples
+///
+/// ```no_run
+/// use graze::ConfigLoader;
+/// use serde::{Serialize, Deserialize};
+///
+/// #[derive(Serialize, Deserialize, Default)]
+/// struct Config {
+///     message: String
+/// }
+///
+/// let config = ConfigLoader::new(|s| toml::from_str(s))
+///     .path("Config.toml")
+///     .or_default(Config::default)
+///     .write_if_missing(|c| toml::to_string(c).map_err(serde::de::Error::custom))
+///     .load()
+///     .expect("Could not load configuration");
+/// ```
+pub struct ConfigLoader<T, E> {
+    deserializer: Deserializer<T, E>,
+    paths: Vec<PathBuf>,
+    default: Option<Box<dyn FnOnce() -> T>>,
+    serializer: Option<Serializer<T, E>>,
+    empty_as_missing: bool,
+    max_size: Option<u64>,
+}
+
+impl<T, E> ConfigLoader<T, E> {
+    /// Create a loader which deserializes the configuration using the given deserializer.
+    pub fn new<D>(deserializer: D) -> Self
+    where
+        D: FnOnce(&str) -> std::result::Result<T, E> + 'static,
+    {
+        Self {
+            deserializer: Box::new(deserializer),
+            paths: Vec::new(),
+            default: None,
+            serializer: None,
+            empty_as_missing: false,
+            max_size: None,
+        }
+    }
+
+    /// Add a path to load the configuration from.
+    ///
+    /// If several paths are added, the configuration is loaded from the first that exists, in the
+    /// order that they were added.
+    pub fn path<P>(mut self, path: P) -> Self
+    where
+        P: Into<PathBuf>,
+    {
+        self.paths.push(path.into());
+        self
+    }
+
+    /// Use the value returned by the given function if none of the paths exist.
+    pub fn or_default<F>(mut self, default: F) -> Self
+    where
+        F: FnOnce() -> T + 'static,
+    {
+        self.default = Some(Box::new(default));
+        self
+    }
+
+    /// Write the default value to the first path, using the given serializer, if none of the paths
+    /// exist.
+    ///
+    /// This has no effect unless a default is set using [ConfigLoader::or_default]. An error
+    /// returned by the serializer is propagated through [ConfigurationError::Serialize].
+    pub fn write_if_missing<S, B>(mut self, serializer: S) -> Self
+    where
+        S: FnOnce(&T) -> std::result::Result<B, E> + 'static,
+        B: AsRef<[u8]>,
+    {
+        self.serializer = Some(Box::new(move |c| {
+            serializer(c).map(|b| b.as_ref().to_vec())
+        }));
+        self
+    }
+
+    /// Treat files which are empty or only contain whitespace as if they did not exist.
+    ///
+    /// This is disabled by default, in which case an empty file is passed to the deserializer like
+    /// any other file. When enabled, an empty file is skipped in favor of the next path or the
+    /// default, and is replaced by the default value if [ConfigLoader::write_if_missing] is used.
+    /// This is useful when the configuration file may have been created with e.g. `touch`.
+    pub fn empty_as_missing(mut self, enabled: bool) -> Self {
+        self.empty_as_missing = enabled;
+        self
+    }
+
+    /// Refuse to load files larger than `limit` bytes.
+    ///
+    /// The size of each file is checked before it is read, and [ConfigurationError::TooLarge] is
+    /// returned if it exceeds the limit. This guards against running out of memory if the path
+    /// accidentally refers to a huge file, such as a log. By default, files of any size are loaded.
+    /// See [load_from_path_bounded](crate::load_from_path_bounded) for details.
+    pub fn max_size(mut self, limit: u64) -> Self {
+        self.max_size = Some(limit);
+        self
+    }
+
+    /// Load the configuration.
+    ///
+    /// If none of the paths exist and no default is set, [ConfigurationError::NotFound] is returned
+    /// listing every path.
+    pub fn load(self) -> Result<T, E> {
+        let mut first_is_empty = false;
+
+        for (i, path) in self.paths.iter().enumerate() {
+            match self.read(path)? {
+                Some(content) if self.empty_as_missing && content.trim().is_empty() => {
+                    first_is_empty |= i == 0;
+                }
+                Some(content) => return load_from_str(&content, self.deserializer),
+                None => {}
+            }
+        }
+
+        let Some(default) = self.default else {
+            return Err(ConfigurationError::NotFound { paths: self.paths });
+        };
+
+        match (self.serializer, self.paths.first()) {
+            (Some(serializer), Some(path)) if first_is_empty => {
+                let data = default();
+                let serialized = serializer(&data).map_err(ConfigurationError::serialize)?;
+                write_atomic(path, &serialized).map_err(|err| ConfigurationError::io(path, err))?;
+
+                Ok(data)
+            }
+            (Some(serializer), Some(path)) => try_load_or_write_with(
+                path,
+                self.deserializer,
+                serializer,
+                default,
+                write_new_atomic,
+            )
+            .map(|(data, _)| data),
+            _ => Ok(default()),
+        }
+    }
+
+    /// Read the file at `path`, returning [None] if it does not exist.
+    fn read(&self, path: &Path) -> Result<Option<String>, E> {
+        let Some(limit) = self.max_size else {
+            return read_if_exists(path);
+        };
+
+        match read_to_string_bounded(path, limit) {
+            Ok(content) => Ok(Some(content)),
+            Err(ConfigurationError::NotFound { .. }) => Ok(None),
+            Err(err) => Err(err),
+        }
+    }
+}
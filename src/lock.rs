@@ -0,0 +1,87 @@
+//! Holding an advisory lock on a configuration file, backed by [fs4].
+
+use std::fs::File;
+use std::io::Read;
+use std::path::{Path, PathBuf};
+
+use fs4::{FileExt, TryLockError};
+
+use crate::{decode_content, load_from_str, read_error, ConfigurationError, Result};
+
+/// An exclusive advisory lock on a configuration file, created by [load_locked].
+///
+/// The lock is released when this is dropped. Being advisory, the lock only prevents other
+/// processes from locking the file, not from reading or writing it.
+#[derive(Debug)]
+pub struct FileLock {
+    file: File,
+    path: PathBuf,
+}
+
+impl FileLock {
+    /// Get the path of the locked file.
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+}
+
+impl Drop for FileLock {
+    fn drop(&mut self) {
+        let _ = FileExt::unlock(&self.file);
+    }
+}
+
+/// Load a configuration from the file at the given path, then hold an exclusive lock on the file
+/// until the returned [FileLock] is dropped.
+///
+/// The lock is acquired before the file is read. If another process (or another [FileLock]) holds
+/// a lock on the file, [ConfigurationError::Locked] is returned rather than waiting for the lock
+/// to be released. See [load_from_path](crate::load_from_path) for details.
+///
+/// # Examples
+///
+/// ```no_run
+/// use serde::Deserialize;
+///
+/// #[derive(Deserialize)]
+/// struct Config {
+///     message: String
+/// }
+///
+/// let (config, _lock): (Config, _) = graze::load_locked("Config.toml", |c| toml::from_str(c))
+///     .expect("Could not load configuration");
+///
+/// // The configuration file stays locked until `_lock` is dropped.
+/// ```
+pub fn load_locked<P, T, E, D>(path: P, deserializer: D) -> Result<(T, FileLock), E>
+where
+    P: AsRef<Path>,
+    D: FnOnce(&str) -> std::result::Result<T, E>,
+{
+    let path = path.as_ref();
+    let file = File::open(path).map_err(|err| read_error(path, err))?;
+
+    match FileExt::try_lock(&file) {
+        Ok(()) => {}
+        Err(TryLockError::WouldBlock) => {
+            return Err(ConfigurationError::Locked {
+                path: path.to_path_buf(),
+            })
+        }
+        Err(TryLockError::Error(err)) => return Err(ConfigurationError::io(path, err)),
+    }
+
+    let lock = FileLock {
+        file,
+        path: path.to_path_buf(),
+    };
+    let mut content = Vec::new();
+    (&lock.file)
+        .read_to_end(&mut content)
+        .map_err(|err| read_error(path, err))?;
+    let content = decode_content(path, content)?;
+
+    let config = load_from_str(&content, deserializer)?;
+
+    Ok((config, lock))
+}
@@ -0,0 +1,477 @@
+//! Variants of the loading functions which also report how the configuration was loaded.
+
+use std::io::Read;
+use std::path::{Path, PathBuf};
+use std::time::SystemTime;
+
+use crate::{
+    decode_content, load_from_path, load_from_str, load_or_partial, open_with_metadata,
+    read_if_exists, try_load_or_write_with, write_new_atomic, ConfigurationError, Result,
+};
+
+/// Information about how a configuration was loaded.
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct LoadMeta {
+    /// The path of the configuration file that was read or written.
+    pub path: PathBuf,
+
+    /// Whether the default configuration was written to [LoadMeta::path].
+    pub wrote_default: bool,
+}
+
+impl LoadMeta {
+    /// Check whether this is the first run of the program, i.e. whether the configuration file did
+    /// not exist and the default value was written to it.
+    ///
+    /// This is the same check as [is_first_run], made at the time the configuration was loaded.
+    pub fn is_first_run(&self) -> bool {
+        self.wrote_default
+    }
+}
+
+/// The fields of a configuration which were taken from its file rather than the default value,
+/// populated by the merge function passed to [load_or_partial_with_report].
+#[derive(Clone, Debug, Default, Eq, PartialEq)]
+pub struct MergeReport {
+    from_file: Vec<String>,
+}
+
+impl MergeReport {
+    /// Record that the field with the given name was taken from the file.
+    pub fn record<S>(&mut self, field: S)
+    where
+        S: Into<String>,
+    {
+        self.from_file.push(field.into());
+    }
+
+    /// Get the names of the fields taken from the file, in the order they were recorded.
+    pub fn from_file(&self) -> &[String] {
+        &self.from_file
+    }
+
+    /// Check whether the field with the given name was taken from the file.
+    pub fn is_from_file(&self, field: &str) -> bool {
+        self.from_file.iter().any(|f| f == field)
+    }
+}
+
+/// A configuration, along with whether it was loaded from a file or is the default value.
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub enum Loaded<T> {
+    /// The configuration was loaded from a file.
+    FromFile(T),
+
+    /// The configuration file did not exist, so the default value was used.
+    Default(T),
+}
+
+impl<T> Loaded<T> {
+    /// Check whether the default value was used.
+    pub fn is_default(&self) -> bool {
+        matches!(self, Self::Default(_))
+    }
+
+    /// Get the configuration, regardless of where it came from.
+    pub fn into_inner(self) -> T {
+        match self {
+            Self::FromFile(config) | Self::Default(config) => config,
+        }
+    }
+}
+
+/// Check whether this is the first run of the program, i.e. whether the configuration file at the
+/// given path does not exist yet.
+///
+/// To check this at the same time as loading the configuration, without the file possibly being
+/// created in the meantime, use [load_or_write_default_with_meta] and [LoadMeta::is_first_run].
+///
+/// # Examples
+///
+/// ```no_run
+/// if graze::is_first_run("Config.toml") {
+///     println!("Welcome! Let's set things up.");
+/// }
+/// ```
+pub fn is_first_run<P>(path: P) -> bool
+where
+    P: AsRef<Path>,
+{
+    !path.as_ref().exists()
+}
+
+/// Load a configuration from the file at the given path, along with information about the load.
+///
+/// See [load_from_path] for details.
+///
+/// # Examples
+///
+/// ```no_run
+/// use serde::Deserialize;
+///
+/// #[derive(Deserialize)]
+/// struct Config {
+///     message: String
+/// }
+///
+/// let (config, meta): (Config, _) =
+///     graze::load_from_path_with_meta("Config.toml", |c| toml::from_str(c))
+///         .expect("Could not load configuration");
+///
+/// println!("Loaded configuration from {}", meta.path.display());
+/// ```
+pub fn load_from_path_with_meta<P, T, E, D>(path: P, deserializer: D) -> Result<(T, LoadMeta), E>
+where
+    P: AsRef<Path>,
+    D: FnOnce(&str) -> std::result::Result<T, E>,
+{
+    let path = path.as_ref();
+    let config = load_from_path(path, deserializer)?;
+
+    let meta = LoadMeta {
+        path: path.to_path_buf(),
+        wrote_default: false,
+    };
+    Ok((config, meta))
+}
+
+/// Load a configuration from the file at the given path, along with the time the file was last
+/// modified.
+///
+/// The modification time is read from the metadata of the file as it is opened for reading, rather
+/// than with a separate call, so it always belongs to the content which was loaded even if the file
+/// is replaced in the meantime. This is useful for e.g. showing when the configuration was last
+/// edited. On platforms where the modification time is unavailable, [ConfigurationError::Io] is
+/// returned. See [load_from_path] for details.
+///
+/// # Examples
+///
+/// ```no_run
+/// use serde::Deserialize;
+///
+/// #[derive(Deserialize)]
+/// struct Config {
+///     message: String
+/// }
+///
+/// let (config, modified): (Config, _) =
+///     graze::load_from_path_with_mtime("Config.toml", |c| toml::from_str(c))
+///         .expect("Could not load configuration");
+///
+/// println!("Configuration last edited: {modified:?}");
+/// ```
+pub fn load_from_path_with_mtime<P, T, E, D>(path: P, deserializer: D) -> Result<(T, SystemTime), E>
+where
+    P: AsRef<Path>,
+    D: FnOnce(&str) -> std::result::Result<T, E>,
+{
+    let path = path.as_ref();
+    let (mut file, metadata) = open_with_metadata(path)?;
+    let modified = metadata
+        .modified()
+        .map_err(|err| ConfigurationError::io(path, err))?;
+
+    let mut content = Vec::with_capacity(metadata.len() as usize);
+    file.read_to_end(&mut content)
+        .map_err(|err| ConfigurationError::io(path, err))?;
+    let config = load_from_str(&decode_content(path, content)?, deserializer)?;
+
+    Ok((config, modified))
+}
+
+/// Load a configuration from the first of the given paths that exists, along with information about
+/// the load.
+///
+/// [LoadMeta::path] holds the candidate that was used. See
+/// [load_from_first_existing](crate::load_from_first_existing) for details.
+pub fn load_from_first_existing_with_meta<I, P, T, E, D>(
+    paths: I,
+    deserializer: D,
+) -> Result<(T, LoadMeta), E>
+where
+    I: IntoIterator<Item = P>,
+    P: AsRef<Path>,
+    D: FnOnce(&str) -> std::result::Result<T, E>,
+{
+    let mut tried = Vec::new();
+
+    for path in paths {
+        let path = path.as_ref();
+
+        if path.exists() {
+            return load_from_path_with_meta(path, deserializer);
+        }
+        tried.push(path.to_path_buf());
+    }
+    Err(ConfigurationError::NotFound { paths: tried })
+}
+
+/// Load a configuration from the file at the given path, or write and use the default value if the
+/// file does not exist, along with information about the load.
+///
+/// [LoadMeta::wrote_default] is `true` if the default value was written. See
+/// [load_or_write_default](crate::load_or_write_default) for details.
+///
+/// # Examples
+///
+/// ```no_run
+/// use serde::{Serialize, Deserialize};
+///
+/// #[derive(Serialize, Deserialize, Default)]
+/// struct Config {
+///     message: String
+/// }
+///
+/// let (config, meta) = graze::load_or_write_default_with_meta(
+///     "Config.toml",
+///     |s| toml::from_str(s),
+///     |c| toml::to_string(c).map_err(serde::de::Error::custom),
+///     Config::default,
+/// )
+/// .expect("Could not load configuration");
+///
+/// if meta.wrote_default {
+///     println!("Wrote new configuration to {}", meta.path.display());
+/// }
+/// ```
+pub fn load_or_write_default_with_meta<P, T, E, D, S, F, B>(
+    path: P,
+    deserializer: D,
+    serializer: S,
+    default: F,
+) -> Result<(T, LoadMeta), E>
+where
+    P: AsRef<Path>,
+    D: FnOnce(&str) -> std::result::Result<T, E>,
+    S: FnOnce(&T) -> std::result::Result<B, E>,
+    B: AsRef<[u8]>,
+    F: FnOnce() -> T,
+{
+    try_load_or_write_with(
+        path.as_ref(),
+        deserializer,
+        serializer,
+        default,
+        write_new_atomic,
+    )
+}
+
+/// Load a configuration from the file at the given path, or write and use the default value if the
+/// file does not exist, calling `on_write` once the default value was written.
+///
+/// `on_write` receives the path of the file and the default value, and is never called if the file
+/// already existed. See [load_or_write_default](crate::load_or_write_default) for details.
+///
+/// # Examples
+///
+/// ```no_run
+/// use serde::{Serialize, Deserialize};
+///
+/// #[derive(Serialize, Deserialize, Default)]
+/// struct Config {
+///     message: String
+/// }
+///
+/// let config = graze::load_or_write_default_with_callback(
+///     "Config.toml",
+///     |s| toml::from_str(s),
+///     |c| toml::to_string(c).map_err(serde::de::Error::custom),
+///     Config::default,
+///     |path, _| println!("Wrote new configuration to {}", path.display()),
+/// )
+/// .expect("Could not load configuration");
+/// ```
+pub fn load_or_write_default_with_callback<P, T, E, D, S, F, B, C>(
+    path: P,
+    deserializer: D,
+    serializer: S,
+    default: F,
+    on_write: C,
+) -> Result<T, E>
+where
+    P: AsRef<Path>,
+    D: FnOnce(&str) -> std::result::Result<T, E>,
+    S: FnOnce(&T) -> std::result::Result<B, E>,
+    B: AsRef<[u8]>,
+    F: FnOnce() -> T,
+    C: FnOnce(&Path, &T),
+{
+    let (config, meta) = load_or_write_default_with_meta(path, deserializer, serializer, default)?;
+
+    if meta.wrote_default {
+        on_write(&meta.path, &config);
+    }
+    Ok(config)
+}
+
+/// Load a configuration from the file at the given path, or use the default value if the file does
+/// not exist, reporting which of the two happened.
+///
+/// Rather than checking whether the file exists beforehand, the file is read and the default value
+/// is only used if the read fails with [ConfigurationError::NotFound].
+///
+/// # Examples
+///
+/// ```no_run
+/// use graze::Loaded;
+/// use serde::Deserialize;
+///
+/// #[derive(Deserialize, Default)]
+/// struct Config {
+///     message: String
+/// }
+///
+/// let loaded =
+///     graze::load_or_default_verbose("Config.toml", |s| toml::from_str(s), Config::default)
+///         .expect("Could not load configuration");
+///
+/// if loaded.is_default() {
+///     println!("No configuration found, using built-in defaults");
+/// }
+/// let config: Config = loaded.into_inner();
+/// ```
+pub fn load_or_default_verbose<P, T, E, D, F>(
+    path: P,
+    deserializer: D,
+    default: F,
+) -> Result<Loaded<T>, E>
+where
+    P: AsRef<Path>,
+    D: FnOnce(&str) -> std::result::Result<T, E>,
+    F: FnOnce() -> T,
+{
+    let path = path.as_ref();
+
+    match load_from_path(path, deserializer) {
+        Ok(config) => Ok(Loaded::FromFile(config)),
+        Err(ConfigurationError::NotFound { .. }) => {
+            debug!(
+                "Configuration file '{}' does not exist, using default",
+                path.display()
+            );
+            Ok(Loaded::Default(default()))
+        }
+        Err(err) => Err(err),
+    }
+}
+
+/// Load a configuration from the file at the given path, or use the default value if the file does
+/// not exist, reporting what [load_or_write_default](crate::load_or_write_default) would write
+/// without writing anything.
+///
+/// If the file does not exist, the serialized default value is returned alongside the default
+/// value, but is never written to the file. If the file exists, it is loaded as usual and [None] is
+/// returned alongside the configuration.
+///
+/// # Examples
+///
+/// ```no_run
+/// use serde::{Serialize, Deserialize};
+///
+/// #[derive(Serialize, Deserialize, Default)]
+/// struct Config {
+///     message: String
+/// }
+///
+/// let (config, preview) = graze::load_or_write_default_dry_run(
+///     "Config.toml",
+///     |s| toml::from_str(s),
+///     |c| toml::to_string(c).map_err(serde::de::Error::custom),
+///     Config::default,
+/// )
+/// .expect("Could not load configuration");
+///
+/// if let Some(preview) = preview {
+///     println!("Would write:\n{}", String::from_utf8_lossy(&preview));
+/// }
+/// ```
+pub fn load_or_write_default_dry_run<P, T, E, D, S, F, B>(
+    path: P,
+    deserializer: D,
+    serializer: S,
+    default: F,
+) -> Result<(T, Option<Vec<u8>>), E>
+where
+    P: AsRef<Path>,
+    D: FnOnce(&str) -> std::result::Result<T, E>,
+    S: FnOnce(&T) -> std::result::Result<B, E>,
+    B: AsRef<[u8]>,
+    F: FnOnce() -> T,
+{
+    if let Some(content) = read_if_exists(path.as_ref())? {
+        return load_from_str(&content, deserializer).map(|config| (config, None));
+    }
+
+    let data = default();
+    let preview = serializer(&data)
+        .map_err(ConfigurationError::serialize)?
+        .as_ref()
+        .to_vec();
+
+    Ok((data, Some(preview)))
+}
+
+/// Load a partial configuration from the file at the given path and merge it over the default
+/// value, reporting which fields were taken from the file.
+///
+/// This behaves like [load_or_partial](crate::load_or_partial), except that the merge function also
+/// receives a [MergeReport], in which it records the name of each field it takes from the partial
+/// configuration. The report is returned alongside the configuration, e.g. to show which settings
+/// the user changed. If the file does not exist, the report is empty.
+///
+/// # Examples
+///
+/// ```no_run
+/// use serde::Deserialize;
+///
+/// #[derive(Default)]
+/// struct Config {
+///     message: String,
+///     volume: u8,
+/// }
+///
+/// #[derive(Deserialize)]
+/// struct PartialConfig {
+///     message: Option<String>,
+///     volume: Option<u8>,
+/// }
+///
+/// let (config, report) = graze::load_or_partial_with_report(
+///     "Config.toml",
+///     |c| toml::from_str::<PartialConfig>(c),
+///     Config::default,
+///     |mut config, partial, report| {
+///         if let Some(message) = partial.message {
+///             config.message = message;
+///             report.record("message");
+///         }
+///         if let Some(volume) = partial.volume {
+///             config.volume = volume;
+///             report.record("volume");
+///         }
+///         config
+///     },
+/// )
+/// .expect("Could not load configuration");
+///
+/// println!("Your overrides: {}", report.from_file().join(", "));
+/// ```
+pub fn load_or_partial_with_report<P, T, U, E, D, F, M>(
+    path: P,
+    deserializer: D,
+    default: F,
+    merge: M,
+) -> Result<(T, MergeReport), E>
+where
+    P: AsRef<Path>,
+    D: FnOnce(&str) -> std::result::Result<U, E>,
+    F: FnOnce() -> T,
+    M: FnOnce(T, U, &mut MergeReport) -> T,
+{
+    let mut report = MergeReport::default();
+    let config = load_or_partial(path, deserializer, default, |config, partial| {
+        merge(config, partial, &mut report)
+    })?;
+
+    Ok((config, report))
+}
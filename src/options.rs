@@ -0,0 +1,257 @@
+//! Options controlling how the path of a configuration file is resolved.
+
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+
+#[cfg(all(unix, feature = "no-follow"))]
+use crate::{decode_content, load_from_str, open_with_metadata_using};
+use crate::{load_from_path, load_or_write_default, save_to_path, ConfigurationError, Result};
+
+/// The maximum number of symbolic links followed when resolving a path which does not exist.
+const MAX_LINKS: usize = 40;
+
+/// Options controlling how the path of a configuration file is resolved before it is read or
+/// written, passed to the functions ending in `_with_options`.
+///
+/// # Examples
+///
+/// ```no_run
+/// use graze::LoadOptions;
+/// use serde::{Serialize, Deserialize};
+///
+/// #[derive(Serialize, Deserialize, Default)]
+/// struct Config {
+///     message: String
+/// }
+///
+/// // Refuse `Config.toml` if it is a symbolic link, rather than reading or writing its target.
+/// let options = LoadOptions {
+///     follow_symlinks: false,
+///     ..LoadOptions::default()
+/// };
+///
+/// let config = graze::load_or_write_default_with_options(
+///     "Config.toml",
+///     |s| toml::from_str(s),
+///     |c| toml::to_string(c).map_err(serde::de::Error::custom),
+///     Config::default,
+///     &options,
+/// )
+/// .expect("Could not load configuration");
+/// ```
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct LoadOptions {
+    /// Whether to follow a symbolic link at the path. If `true`, the link is resolved to its target
+    /// before the path is used, even if the target does not exist yet, so that a default value is
+    /// written to the target rather than failing because the link exists, and a saved
+    /// configuration replaces the target rather than the link. If `false`, a path which is a
+    /// symbolic link is refused with an [io::ErrorKind::InvalidInput] error. Symbolic links among
+    /// the parent directories of the path are always followed. Defaults to `true`.
+    pub follow_symlinks: bool,
+
+    /// Whether to resolve the path to its canonical form before it is used, making it absolute and
+    /// following symbolic links among its parent directories. The path is resolved even if the
+    /// file does not exist yet. Defaults to `false`.
+    pub canonicalize: bool,
+
+    /// Whether to expand a leading `~` in the path to the home directory, using
+    /// [expand_tilde](crate::expand_tilde). The path is expanded before it is resolved in any
+    /// other way. Defaults to `false`.
+    #[cfg(feature = "dirs")]
+    pub expand_tilde: bool,
+}
+
+impl Default for LoadOptions {
+    fn default() -> Self {
+        Self {
+            follow_symlinks: true,
+            canonicalize: false,
+            #[cfg(feature = "dirs")]
+            expand_tilde: false,
+        }
+    }
+}
+
+impl LoadOptions {
+    /// Resolve `path` according to these options.
+    fn resolve<E>(&self, path: &Path) -> Result<PathBuf, E> {
+        #[cfg(feature = "dirs")]
+        let expanded = self.expand_tilde.then(|| crate::expand_tilde(path));
+        #[cfg(feature = "dirs")]
+        let path = expanded.as_deref().unwrap_or(path);
+
+        let path = match (self.follow_symlinks, is_symlink(path)) {
+            (true, true) => follow_links(path),
+            (false, true) => {
+                return Err(ConfigurationError::io(
+                    path,
+                    io::Error::new(
+                        io::ErrorKind::InvalidInput,
+                        "The configuration file is a symbolic link",
+                    ),
+                ))
+            }
+            (_, false) => path.to_path_buf(),
+        };
+
+        if !self.canonicalize {
+            return Ok(path);
+        }
+
+        match fs::canonicalize(&path) {
+            Ok(path) => Ok(path),
+            Err(err) if err.kind() == io::ErrorKind::NotFound => Ok(resolve_missing(&path)),
+            Err(err) => Err(ConfigurationError::io(&path, err)),
+        }
+    }
+}
+
+/// Check whether `path` itself is a symbolic link.
+fn is_symlink(path: &Path) -> bool {
+    fs::symlink_metadata(path).is_ok_and(|metadata| metadata.file_type().is_symlink())
+}
+
+/// Follow the symbolic link at `path`, and any link it points to, until a path which is not a link
+/// is reached. The target does not need to exist.
+fn follow_links(path: &Path) -> PathBuf {
+    let mut path = path.to_path_buf();
+
+    for _ in 0..MAX_LINKS {
+        let Ok(target) = fs::read_link(&path) else {
+            break;
+        };
+        path = match path.parent() {
+            Some(parent) => parent.join(target),
+            None => target,
+        };
+    }
+
+    path
+}
+
+/// Resolve a path which cannot be canonicalized because it, or the target of the symbolic link at
+/// it, does not exist.
+///
+/// Symbolic links are followed until a path which is not a link is reached, whose parent directory
+/// is then canonicalized if it exists.
+fn resolve_missing(path: &Path) -> PathBuf {
+    let path = follow_links(path);
+
+    let canonical_parent = path
+        .parent()
+        .filter(|parent| !parent.as_os_str().is_empty())
+        .and_then(|parent| fs::canonicalize(parent).ok());
+
+    match (canonical_parent, path.file_name()) {
+        (Some(parent), Some(name)) => parent.join(name),
+        _ => path,
+    }
+}
+
+/// Load a configuration from the file at the given path, resolved according to the given options.
+///
+/// See [load_from_path] for details.
+pub fn load_from_path_with_options<P, T, E, D>(
+    path: P,
+    deserializer: D,
+    options: &LoadOptions,
+) -> Result<T, E>
+where
+    P: AsRef<Path>,
+    D: FnOnce(&str) -> std::result::Result<T, E>,
+{
+    load_from_path(options.resolve(path.as_ref())?, deserializer)
+}
+
+/// Load a configuration from the file at the given path, refusing to follow a symbolic link at the
+/// path itself.
+///
+/// The file is opened with `O_NOFOLLOW`, so the open fails if the last component of the path is a
+/// symbolic link, and the check cannot be raced by replacing the file with a link after it was
+/// checked, as it can with [LoadOptions::follow_symlinks]. A symbolic link is reported as
+/// [ConfigurationError::Io], with an error of kind [io::ErrorKind::FilesystemLoop] on most
+/// platforms. Symbolic links among the parent directories of the path are still followed.
+///
+/// This is useful for programs running with elevated privileges, e.g. setuid programs reading a
+/// configuration file from a location the user controls, which must not be tricked into reading
+/// another file (such as `/etc/shadow`) through a link. See [load_from_path] for details.
+///
+/// # Examples
+///
+/// ```no_run
+/// use serde::Deserialize;
+///
+/// #[derive(Deserialize)]
+/// struct Config {
+///     message: String
+/// }
+///
+/// let config: Config = graze::load_from_path_no_follow("Config.toml", |c| toml::from_str(c))
+///     .expect("Could not load configuration");
+/// ```
+#[cfg(all(unix, feature = "no-follow"))]
+pub fn load_from_path_no_follow<P, T, E, D>(path: P, deserializer: D) -> Result<T, E>
+where
+    P: AsRef<Path>,
+    D: FnOnce(&str) -> std::result::Result<T, E>,
+{
+    use std::fs::OpenOptions;
+    use std::io::Read;
+    use std::os::unix::fs::OpenOptionsExt;
+
+    let path = path.as_ref();
+    let (mut file, metadata) = open_with_metadata_using(
+        path,
+        OpenOptions::new().read(true).custom_flags(libc::O_NOFOLLOW),
+    )?;
+
+    let mut content = Vec::with_capacity(metadata.len() as usize);
+    file.read_to_end(&mut content)
+        .map_err(|err| ConfigurationError::io(path, err))?;
+
+    load_from_str(&decode_content(path, content)?, deserializer)
+}
+
+/// Load a configuration from the file at the given path, resolved according to the given options,
+/// or write and use the default value if the file does not exist.
+///
+/// See [load_or_write_default] for details.
+pub fn load_or_write_default_with_options<P, T, E, D, S, F, B>(
+    path: P,
+    deserializer: D,
+    serializer: S,
+    default: F,
+    options: &LoadOptions,
+) -> Result<T, E>
+where
+    P: AsRef<Path>,
+    D: FnOnce(&str) -> std::result::Result<T, E>,
+    S: FnOnce(&T) -> std::result::Result<B, E>,
+    B: AsRef<[u8]>,
+    F: FnOnce() -> T,
+{
+    load_or_write_default(
+        options.resolve(path.as_ref())?,
+        deserializer,
+        serializer,
+        default,
+    )
+}
+
+/// Save a configuration to the file at the given path, resolved according to the given options.
+///
+/// See [save_to_path] for details.
+pub fn save_to_path_with_options<P, T, E, S, B>(
+    path: P,
+    value: &T,
+    serializer: S,
+    options: &LoadOptions,
+) -> Result<(), E>
+where
+    P: AsRef<Path>,
+    S: FnOnce(&T) -> std::result::Result<B, E>,
+    B: AsRef<[u8]>,
+{
+    save_to_path(options.resolve(path.as_ref())?, value, serializer)
+}
@@ -0,0 +1,102 @@
+//! Control over the permissions of written configuration files.
+
+use std::fs::OpenOptions;
+use std::io;
+#[cfg(unix)]
+use std::os::unix::fs::{OpenOptionsExt, PermissionsExt};
+use std::path::Path;
+
+#[cfg(all(windows, feature = "windows-acl"))]
+use crate::acl;
+use crate::{try_load_or_write_with, write_new_secured, Result};
+
+/// The mode of configuration files written by [load_or_write_default_with_mode], if no other mode
+/// is desired: readable and writable by the owner only.
+pub const DEFAULT_MODE: u32 = 0o600;
+
+/// Load a configuration from the file at the given path, or use the default value if the file does
+/// not exist.
+///
+/// If the file does not exist, the default value will be written to the file at the given path,
+/// which is created with the given Unix permission `mode` (e.g. [DEFAULT_MODE]) rather than
+/// inheriting the permissions implied by the process umask alone. This is useful for configuration
+/// files holding secrets. On non-Unix platforms, the mode is ignored.
+///
+/// On Windows with the `windows-acl` feature, the access control list of the file is instead
+/// replaced with one granting access to the current user only, rather than inheriting the list of
+/// its directory. As with the mode, this is done before the default value is written to the file.
+///
+/// See [load_or_write_default](crate::load_or_write_default) for details.
+///
+/// # Examples
+///
+/// ```no_run
+/// use serde::{Serialize, Deserialize};
+///
+/// #[derive(Serialize, Deserialize, Default)]
+/// struct Config {
+///     token: String
+/// }
+///
+/// let config = graze::load_or_write_default_with_mode(
+///     "Config.toml",
+///     |s| toml::from_str(s),
+///     |c| toml::to_string(c).map_err(serde::de::Error::custom),
+///     Config::default,
+///     graze::DEFAULT_MODE,
+/// );
+/// ```
+#[cfg_attr(not(unix), allow(unused_mut, unused_variables))]
+pub fn load_or_write_default_with_mode<P, T, E, D, S, F, B>(
+    path: P,
+    deserializer: D,
+    serializer: S,
+    default: F,
+    mode: u32,
+) -> Result<T, E>
+where
+    P: AsRef<Path>,
+    D: FnOnce(&str) -> std::result::Result<T, E>,
+    S: FnOnce(&T) -> std::result::Result<B, E>,
+    B: AsRef<[u8]>,
+    F: FnOnce() -> T,
+{
+    let mut options = OpenOptions::new();
+
+    #[cfg(unix)]
+    options.mode(mode);
+
+    let write =
+        |path: &Path, contents: &[u8]| write_new_secured(path, contents, &options, restrict);
+    try_load_or_write_with(path.as_ref(), deserializer, serializer, default, write)
+        .map(|(data, _)| data)
+}
+
+/// Restrict access to the newly created file at `path` to the current user, with the `windows-acl`
+/// feature on Windows. This does nothing elsewhere, where the mode restricts access instead.
+fn restrict(path: &Path) -> io::Result<()> {
+    #[cfg(all(windows, feature = "windows-acl"))]
+    return acl::restrict_to_owner(path);
+
+    #[cfg(not(all(windows, feature = "windows-acl")))]
+    {
+        let _ = path;
+        Ok(())
+    }
+}
+
+/// Apply the Unix permission `mode` to the newly created file at `path`, regardless of the process
+/// umask.
+///
+/// On other platforms the mode is ignored, and access is restricted to the current user as by
+/// [load_or_write_default_with_mode] instead.
+pub(crate) fn apply_mode(path: &Path, mode: u32) -> io::Result<()> {
+    #[cfg(unix)]
+    return std::fs::set_permissions(path, std::fs::Permissions::from_mode(mode));
+
+    #[cfg(not(unix))]
+    {
+        let _ = mode;
+        restrict(path)
+    }
+}
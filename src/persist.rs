@@ -0,0 +1,137 @@
+//! Saving a configuration with a chosen combination of safety measures.
+
+use std::fs::{self, OpenOptions};
+use std::io::{self, Write};
+use std::path::Path;
+
+use crate::{
+    apply_mode, create_parent_dirs, sibling_with_suffix, write_atomic_secured, ConfigurationError,
+    Result, DEFAULT_BACKUP_SUFFIX,
+};
+
+/// Options controlling how [persist] writes a configuration file.
+///
+/// The defaults write the file atomically, creating missing parent directories, without a backup
+/// and without changing the mode implied by the process umask.
+///
+/// # Examples
+///
+/// ```no_run
+/// use graze::PersistOptions;
+/// use serde::Serialize;
+///
+/// #[derive(Serialize)]
+/// struct Config {
+///     token: String
+/// }
+///
+/// let options = PersistOptions {
+///     backup: true,
+///     mode: Some(graze::DEFAULT_MODE),
+///     ..PersistOptions::default()
+/// };
+///
+/// let config = Config { token: "secret".to_string() };
+///
+/// graze::persist("Config.toml", &config, |c| toml::to_string(c), &options)
+///     .expect("Could not save configuration");
+/// ```
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct PersistOptions {
+    /// Whether to copy the existing file to a sibling with [DEFAULT_BACKUP_SUFFIX] appended to its
+    /// name before replacing it, replacing any previous backup. Defaults to `false`.
+    pub backup: bool,
+
+    /// Whether to write the file atomically, as [save_to_path](crate::save_to_path) does. If
+    /// `false`, the file is truncated and written in place, which keeps its identity (e.g. hard
+    /// links to it) but can leave it partially written after a crash. Defaults to `true`.
+    pub atomic: bool,
+
+    /// Whether to create the missing parent directories of the file. Defaults to `true`.
+    pub create_dirs: bool,
+
+    /// The Unix permission mode to give the file (e.g. [DEFAULT_MODE](crate::DEFAULT_MODE)), or
+    /// [None] to keep the permissions of an existing file, or leave those of a new file to the
+    /// process umask. The mode is applied before the configuration is written to the file. On
+    /// other platforms, see
+    /// [load_or_write_default_with_mode](crate::load_or_write_default_with_mode). Defaults to
+    /// [None].
+    pub mode: Option<u32>,
+}
+
+impl Default for PersistOptions {
+    fn default() -> Self {
+        Self {
+            backup: false,
+            atomic: true,
+            create_dirs: true,
+            mode: None,
+        }
+    }
+}
+
+/// Save a configuration to the file at the given path, replacing the file if it already exists,
+/// using the given options.
+///
+/// This bundles the steps of saving a configuration safely which are otherwise spread over
+/// [save_to_path](crate::save_to_path),
+/// [save_to_path_with_backup](crate::save_to_path_with_backup) and
+/// [load_or_write_default_with_mode](crate::load_or_write_default_with_mode), e.g. for saving
+/// settings edited by the user at runtime. The configuration is serialized before anything is
+/// changed on disk, so a serializer error leaves the file and its backup untouched. An error
+/// returned by the serializer is propagated through [ConfigurationError::Serialize].
+pub fn persist<P, T, E, S, B>(
+    path: P,
+    value: &T,
+    serializer: S,
+    options: &PersistOptions,
+) -> Result<(), E>
+where
+    P: AsRef<Path>,
+    S: FnOnce(&T) -> std::result::Result<B, E>,
+    B: AsRef<[u8]>,
+{
+    let path = path.as_ref();
+    let data = serializer(value).map_err(ConfigurationError::serialize)?;
+
+    if options.create_dirs {
+        create_parent_dirs(path).map_err(|err| ConfigurationError::io(path, err))?;
+    }
+
+    if options.backup && path.exists() {
+        let backup = sibling_with_suffix(path, DEFAULT_BACKUP_SUFFIX);
+        fs::copy(path, &backup).map_err(|err| ConfigurationError::io(&backup, err))?;
+    }
+
+    let secure = |path: &Path| match options.mode {
+        Some(mode) => apply_mode(path, mode),
+        None => Ok(()),
+    };
+
+    let result = if options.atomic {
+        write_atomic_secured(path, data.as_ref(), &OpenOptions::new(), secure)
+    } else {
+        write_in_place(path, data.as_ref(), secure)
+    };
+    result.map_err(|err| ConfigurationError::io(path, err))
+}
+
+/// Truncate the file at `path`, creating it if needed, and write `contents` to it.
+///
+/// `secure` is called with `path` once the file is opened, before anything is written to it.
+fn write_in_place<S>(path: &Path, contents: &[u8], secure: S) -> io::Result<()>
+where
+    S: Fn(&Path) -> io::Result<()>,
+{
+    #[cfg(feature = "gzip")]
+    let contents = &*crate::gzip::compress_for(path, contents)?;
+
+    let mut file = OpenOptions::new()
+        .write(true)
+        .create(true)
+        .truncate(true)
+        .open(path)?;
+
+    secure(path)?;
+    file.write_all(contents)
+}
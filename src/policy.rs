@@ -0,0 +1,125 @@
+//! Loading a configuration with a chosen behavior for when its file does not exist.
+
+use std::path::Path;
+
+use crate::{
+    load_logged, load_or_default_verbose, read_to_string, try_load_or_write_with, write_new_atomic,
+    Result,
+};
+
+type DefaultFn<'a, T> = Box<dyn FnOnce() -> T + 'a>;
+type Serializer<'a, T, E> = Box<dyn FnOnce(&T) -> std::result::Result<Vec<u8>, E> + 'a>;
+
+/// What [load_with_policy] does when the configuration file does not exist.
+///
+/// [load_from_path](crate::load_from_path), [load_or_default](crate::load_or_default) and
+/// [load_or_write_default](crate::load_or_write_default) each correspond to one of these policies,
+/// so a policy can be chosen at runtime (e.g. from a command line flag) rather than by calling a
+/// different function.
+pub enum MissingPolicy<'a, T, E> {
+    /// Return [ConfigurationError::NotFound](crate::ConfigurationError::NotFound), as
+    /// [load_from_path](crate::load_from_path) does.
+    Error,
+
+    /// Use the value returned by the function, as [load_or_default](crate::load_or_default) does.
+    Default(DefaultFn<'a, T>),
+
+    /// Use the value returned by `default` and write it to the file using `serializer`, as
+    /// [load_or_write_default](crate::load_or_write_default) does.
+    WriteDefault {
+        /// The serializer the default value is written with.
+        serializer: Serializer<'a, T, E>,
+
+        /// The function returning the default value.
+        default: DefaultFn<'a, T>,
+    },
+}
+
+impl<'a, T, E> MissingPolicy<'a, T, E> {
+    /// Create a [MissingPolicy::Default] policy using the value returned by the given function.
+    pub fn default<F>(default: F) -> Self
+    where
+        F: FnOnce() -> T + 'a,
+    {
+        Self::Default(Box::new(default))
+    }
+
+    /// Create a [MissingPolicy::WriteDefault] policy using the value returned by `default`, written
+    /// to the file using `serializer`.
+    pub fn write_default<S, F, B>(serializer: S, default: F) -> Self
+    where
+        S: FnOnce(&T) -> std::result::Result<B, E> + 'a,
+        B: AsRef<[u8]>,
+        F: FnOnce() -> T + 'a,
+    {
+        Self::WriteDefault {
+            serializer: Box::new(|c| serializer(c).map(|data| data.as_ref().to_vec())),
+            default: Box::new(default),
+        }
+    }
+}
+
+/// Load a configuration from the file at the given path, handling a missing file according to the
+/// given policy.
+///
+/// Any failure other than the file not existing is returned as an error, whatever the policy. See
+/// [load_from_path](crate::load_from_path), [load_or_default](crate::load_or_default) and
+/// [load_or_write_default](crate::load_or_write_default) for details of each policy.
+///
+/// # Examples
+///
+/// ```no_run
+/// use graze::MissingPolicy;
+/// use serde::{Serialize, Deserialize};
+///
+/// #[derive(Serialize, Deserialize, Default)]
+/// struct Config {
+///     message: String
+/// }
+///
+/// let policy = if std::env::args().any(|arg| arg == "--init") {
+///     MissingPolicy::write_default(
+///         |c| toml::to_string(c).map_err(serde::de::Error::custom),
+///         Config::default,
+///     )
+/// } else {
+///     MissingPolicy::Error
+/// };
+///
+/// let config = graze::load_with_policy("Config.toml", |s| toml::from_str(s), policy)
+///     .expect("Could not load configuration");
+/// ```
+pub fn load_with_policy<P, T, E, D>(
+    path: P,
+    deserializer: D,
+    policy: MissingPolicy<'_, T, E>,
+) -> Result<T, E>
+where
+    P: AsRef<Path>,
+    D: FnOnce(&str) -> std::result::Result<T, E>,
+{
+    match policy {
+        MissingPolicy::Error => {
+            let read = |path: &Path| {
+                read_to_string(path).inspect_err(|_| {
+                    debug!("Could not read configuration file '{}'", path.display());
+                })
+            };
+            load_logged(path.as_ref(), read, deserializer)
+        }
+        MissingPolicy::Default(default) => {
+            load_or_default_verbose(path, deserializer, default).map(|loaded| loaded.into_inner())
+        }
+        MissingPolicy::WriteDefault {
+            serializer,
+            default,
+        } => try_load_or_write_with(
+            path.as_ref(),
+            deserializer,
+            serializer,
+            default,
+            write_new_atomic,
+        )
+        .map(|(data, _)| data),
+    }
+}
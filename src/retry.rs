@@ -0,0 +1,80 @@
+//! Retrying reads which fail with transient IO errors.
+
+use std::io;
+use std::path::Path;
+use std::thread;
+use std::time::Duration;
+
+use crate::{load_from_str, read_to_string, ConfigurationError, Result};
+
+/// Load a configuration from the file at the given path, retrying reads which fail transiently.
+///
+/// If reading the file fails with [io::ErrorKind::Interrupted], [io::ErrorKind::WouldBlock] or
+/// [io::ErrorKind::TimedOut], the read is retried up to `retries` times, sleeping for `delay`
+/// before each retry. Once the retries are exhausted, the last [ConfigurationError::Io] is
+/// returned. Other errors, including deserializer errors, are never retried.
+///
+/// # Examples
+///
+/// ```no_run
+/// use std::time::Duration;
+///
+/// use serde::Deserialize;
+///
+/// #[derive(Deserialize)]
+/// struct Config {
+///     message: String
+/// }
+///
+/// let config: Config = graze::load_from_path_retry(
+///     "/mnt/share/Config.toml",
+///     |c| toml::from_str(c),
+///     3,
+///     Duration::from_millis(100),
+/// )
+/// .expect("Could not load configuration");
+/// ```
+pub fn load_from_path_retry<P, T, E, D>(
+    path: P,
+    deserializer: D,
+    retries: u32,
+    delay: Duration,
+) -> Result<T, E>
+where
+    P: AsRef<Path>,
+    D: FnOnce(&str) -> std::result::Result<T, E>,
+{
+    let path = path.as_ref();
+    let content = read_with_retry(retries, delay, || read_to_string(path))?;
+
+    load_from_str(&content, deserializer)
+}
+
+/// Call `read` until it succeeds, fails with a non-transient error, or has been retried `retries`
+/// times, sleeping for `delay` before each retry.
+pub(crate) fn read_with_retry<E, R>(retries: u32, delay: Duration, mut read: R) -> Result<String, E>
+where
+    R: FnMut() -> Result<String, E>,
+{
+    let mut attempt = 0;
+
+    loop {
+        match read() {
+            Err(ConfigurationError::Io { ref source, .. })
+                if attempt < retries && is_transient(source.kind()) =>
+            {
+                attempt += 1;
+                thread::sleep(delay);
+            }
+            result => return result,
+        }
+    }
+}
+
+/// Check whether an IO error of the given kind may not recur when retrying the operation.
+fn is_transient(kind: io::ErrorKind) -> bool {
+    matches!(
+        kind,
+        io::ErrorKind::Interrupted | io::ErrorKind::WouldBlock | io::ErrorKind::TimedOut
+    )
+}
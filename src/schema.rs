@@ -0,0 +1,41 @@
+//! Exporting a [JSON Schema](https://json-schema.org) of a configuration type, backed by
+//! [schemars].
+
+use std::path::Path;
+
+use schemars::JsonSchema;
+
+use crate::{create_parent_dirs, write_atomic, ConfigurationError, Result};
+
+/// Write a JSON Schema describing the configuration type `T` to the file at the given path.
+///
+/// The schema is generated with [schemars::schema_for] and written as pretty-printed JSON, so that
+/// editors can offer completion and validation while a configuration file is being edited. Any
+/// missing parent directories of the path are created, and an existing file is replaced
+/// atomically, as with [save_to_path](crate::save_to_path).
+///
+/// # Examples
+///
+/// ```no_run
+/// use schemars::JsonSchema;
+/// use serde::Deserialize;
+///
+/// #[derive(Deserialize, JsonSchema)]
+/// struct Config {
+///     message: String
+/// }
+///
+/// graze::write_schema::<Config, _>("Config.schema.json").expect("Could not write schema");
+/// ```
+pub fn write_schema<T, P>(path: P) -> Result<(), serde_json::Error>
+where
+    T: JsonSchema,
+    P: AsRef<Path>,
+{
+    let path = path.as_ref();
+    let schema = schemars::schema_for!(T);
+    let data = serde_json::to_vec_pretty(&schema).map_err(ConfigurationError::serialize)?;
+
+    create_parent_dirs(path).map_err(|err| ConfigurationError::io(path, err))?;
+    write_atomic(path, &data).map_err(|err| ConfigurationError::io(path, err))
+}
@@ -0,0 +1,107 @@
+//! A configuration shared between threads, which can be reloaded while it is being read.
+
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, RwLock, RwLockReadGuard};
+
+use crate::{load_from_path, Result};
+
+type Deserializer<T, E> = Box<dyn Fn(&str) -> std::result::Result<T, E> + Send + Sync>;
+
+/// A configuration shared between threads, which can be reloaded from its file while other threads
+/// are reading it.
+///
+/// Cloning a shared configuration is cheap, and every clone refers to the same configuration.
+/// [SharedConfig::reload] loads the file before swapping the configuration under a write lock, so
+/// readers are only blocked for the swap itself, and always see either the old or the new
+/// configuration as a whole.
+///
+/// # Examples
+///
+/// ```no_run
+/// use std::thread;
+///
+/// use graze::SharedConfig;
+/// use serde::Deserialize;
+///
+/// #[derive(Deserialize)]
+/// struct Config {
+///     message: String
+/// }
+///
+/// let config = SharedConfig::load("Config.toml", |c| toml::from_str::<Config>(c))
+///     .expect("Could not load configuration");
+///
+/// let reader = config.clone();
+/// thread::spawn(move || println!("{}", reader.read().message));
+///
+/// config.reload().expect("Could not reload configuration");
+/// ```
+pub struct SharedConfig<T, E> {
+    inner: Arc<Inner<T, E>>,
+}
+
+struct Inner<T, E> {
+    value: RwLock<T>,
+    path: PathBuf,
+    deserializer: Deserializer<T, E>,
+}
+
+impl<T, E> Clone for SharedConfig<T, E> {
+    fn clone(&self) -> Self {
+        Self {
+            inner: Arc::clone(&self.inner),
+        }
+    }
+}
+
+impl<T, E> SharedConfig<T, E> {
+    /// Load the configuration from the file at the given path.
+    ///
+    /// See [load_from_path] for details.
+    pub fn load<P, D>(path: P, deserializer: D) -> Result<Self, E>
+    where
+        P: AsRef<Path>,
+        D: Fn(&str) -> std::result::Result<T, E> + Send + Sync + 'static,
+    {
+        let path = path.as_ref().to_path_buf();
+        let value = load_from_path(&path, &deserializer)?;
+
+        Ok(Self {
+            inner: Arc::new(Inner {
+                value: RwLock::new(value),
+                path,
+                deserializer: Box::new(deserializer),
+            }),
+        })
+    }
+
+    /// Lock the configuration for reading.
+    ///
+    /// A reload waits until the returned guard is dropped, so the guard should not be held for
+    /// longer than needed.
+    pub fn read(&self) -> RwLockReadGuard<'_, T> {
+        self.inner
+            .value
+            .read()
+            .unwrap_or_else(|poisoned| poisoned.into_inner())
+    }
+
+    /// Load the configuration from its file again, replacing the current configuration.
+    ///
+    /// If loading fails, the current configuration is kept and the error is returned.
+    pub fn reload(&self) -> Result<(), E> {
+        let value = load_from_path(&self.inner.path, &self.inner.deserializer)?;
+
+        *self
+            .inner
+            .value
+            .write()
+            .unwrap_or_else(|poisoned| poisoned.into_inner()) = value;
+        Ok(())
+    }
+
+    /// Get the path of the configuration file.
+    pub fn path(&self) -> &Path {
+        &self.inner.path
+    }
+}
@@ -0,0 +1,83 @@
+//! Reloading a [SharedConfig] when the process receives `SIGHUP`, backed by [signal_hook].
+
+use std::fmt::Display;
+use std::thread;
+
+use signal_hook::consts::SIGHUP;
+use signal_hook::iterator::{Handle, Signals};
+
+use crate::{Result, SharedConfig};
+
+/// A handle to a [SharedConfig] being reloaded on `SIGHUP`, created by [reload_on_sighup].
+///
+/// The configuration stops being reloaded when the handle is dropped. The signal handler itself
+/// stays installed, so `SIGHUP` no longer terminates the process afterwards.
+pub struct SighupReloader {
+    handle: Handle,
+}
+
+impl Drop for SighupReloader {
+    fn drop(&mut self) {
+        self.handle.close();
+    }
+}
+
+/// Reload the given shared configuration from its file whenever the process receives `SIGHUP`, as
+/// daemons conventionally do.
+///
+/// The configuration is reloaded on a background thread using [SharedConfig::reload]. A reload
+/// which fails (e.g. because the file is incorrect) keeps the current configuration; the error is
+/// logged with the `log` feature, and the configuration keeps being reloaded on later signals.
+///
+/// An error installing the signal handler is returned as [ConfigurationError::Io].
+///
+/// [ConfigurationError::Io]: crate::ConfigurationError::Io
+///
+/// # Examples
+///
+/// ```no_run
+/// use graze::SharedConfig;
+/// use serde::Deserialize;
+///
+/// #[derive(Deserialize)]
+/// struct Config {
+///     message: String
+/// }
+///
+/// let config = SharedConfig::load("Config.toml", |c| toml::from_str::<Config>(c))
+///     .expect("Could not load configuration");
+///
+/// let _reloader = graze::reload_on_sighup(config.clone())
+///     .expect("Could not install signal handler");
+///
+/// // `kill -HUP <pid>` now reloads the configuration until `_reloader` is dropped.
+/// ```
+pub fn reload_on_sighup<T, E>(shared: SharedConfig<T, E>) -> Result<SighupReloader, E>
+where
+    T: Send + Sync + 'static,
+    E: Display + 'static,
+{
+    let mut signals = Signals::new([SIGHUP])?;
+    let handle = signals.handle();
+
+    thread::spawn(move || {
+        for _ in signals.forever() {
+            match shared.reload() {
+                Ok(()) => {
+                    debug!(
+                        "Reloaded configuration file '{}' on SIGHUP",
+                        shared.path().display()
+                    );
+                }
+                Err(_err) => {
+                    warn!(
+                        "Could not reload configuration file '{}' on SIGHUP: {_err}",
+                        shared.path().display()
+                    );
+                }
+            }
+        }
+    });
+
+    Ok(SighupReloader { handle })
+}
@@ -0,0 +1,116 @@
+//! Formatting deserializer errors along with the part of the content they occurred in.
+
+use std::fmt::Display;
+
+/// A deserializer error which knows where in the content it occurred.
+///
+/// This is implemented for the errors of the `toml`, `json` and `yaml` features' formats when the
+/// respective feature is enabled.
+pub trait ErrorPosition {
+    /// Get the byte offset into `content` at which the error occurred, if known.
+    fn offset(&self, content: &str) -> Option<usize>;
+}
+
+#[cfg(feature = "toml")]
+impl ErrorPosition for ::toml::de::Error {
+    fn offset(&self, content: &str) -> Option<usize> {
+        let (line, column) = self.line_col()?;
+        Some(offset_of(content, line, column))
+    }
+}
+
+#[cfg(feature = "json")]
+impl ErrorPosition for serde_json::Error {
+    fn offset(&self, content: &str) -> Option<usize> {
+        match (self.line(), self.column()) {
+            (0, _) => None,
+            (line, column) => Some(offset_of(content, line - 1, column.saturating_sub(1))),
+        }
+    }
+}
+
+#[cfg(feature = "yaml")]
+impl ErrorPosition for serde_yaml::Error {
+    fn offset(&self, content: &str) -> Option<usize> {
+        let location = self.location()?;
+        Some(offset_of(
+            content,
+            location.line().saturating_sub(1),
+            location.column().saturating_sub(1),
+        ))
+    }
+}
+
+/// Format a deserializer error along with the line of `content` it occurred in, marking the
+/// position of the error with a caret.
+///
+/// If the error does not know its position, only the error itself is formatted.
+///
+/// # Examples
+///
+/// ```
+/// use std::fmt::{self, Display, Formatter};
+///
+/// use graze::ErrorPosition;
+///
+/// struct MissingValue(usize);
+///
+/// impl Display for MissingValue {
+///     fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+///         write!(f, "Missing value")
+///     }
+/// }
+///
+/// impl ErrorPosition for MissingValue {
+///     fn offset(&self, _: &str) -> Option<usize> {
+///         Some(self.0)
+///     }
+/// }
+///
+/// let content = "message = 'Hello, world!'\nrange = ";
+///
+/// assert_eq!(
+///     graze::format_parse_error(content, &MissingValue(34)),
+///     "Missing value\n --> line 2, column 9\n  |\n2 | range = \n  |         ^",
+/// );
+/// ```
+pub fn format_parse_error<E>(content: &str, err: &E) -> String
+where
+    E: ErrorPosition + Display,
+{
+    let Some(offset) = err.offset(content) else {
+        return err.to_string();
+    };
+    let offset = floor_char_boundary(content, offset.min(content.len()));
+
+    let start = content[..offset].rfind('\n').map_or(0, |i| i + 1);
+    let end = content[offset..]
+        .find('\n')
+        .map_or(content.len(), |i| offset + i);
+
+    let line = content[..start].matches('\n').count() + 1;
+    let column = content[start..offset].chars().count() + 1;
+    let gutter = " ".repeat(line.to_string().len());
+
+    format!(
+        "{err}\n{gutter}--> line {line}, column {column}\n{gutter} |\n{line} | {}\n{gutter} | {}^",
+        content[start..end].trim_end_matches('\r'),
+        " ".repeat(column - 1),
+    )
+}
+
+/// Get the byte offset into `content` of the given 0-based line and byte column.
+#[cfg(any(feature = "toml", feature = "json", feature = "yaml"))]
+fn offset_of(content: &str, line: usize, column: usize) -> usize {
+    let start: usize = content.split_inclusive('\n').take(line).map(str::len).sum();
+
+    (start + column).min(content.len())
+}
+
+/// Get the largest character boundary of `content` which is not greater than `index`.
+fn floor_char_boundary(content: &str, mut index: usize) -> usize {
+    while !content.is_char_boundary(index) {
+        index -= 1;
+    }
+    index
+}
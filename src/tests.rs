@@ -1,34 +1,3191 @@
-use std::fs;
+use std::error::Error;
+use std::io::Cursor;
+use std::path::{Path, PathBuf};
+use std::time::Duration;
+use std::{fs, io};
 
 use serde::{Deserialize, Serialize};
 
-#[derive(Serialize, Deserialize, Default)]
+use crate::{ConfigLoader, ConfigurationError, Expansion, LineEnding, Loaded, MissingPolicy};
+
+#[derive(Serialize, Deserialize, Default, PartialEq, Debug, Clone)]
 struct Config {
     range: usize,
 }
 
+/// Serialize `value` to TOML, reporting errors as deserializer errors for use alongside
+/// [toml::from_str].
+fn to_toml<T: Serialize>(value: &T) -> Result<String, toml::de::Error> {
+    toml::to_string(value).map_err(serde::de::Error::custom)
+}
+
+/// Serve the given raw HTTP responses, one per connection, on a local port.
+///
+/// Returns the URL of the server and a handle to the serving thread, which returns the raw requests
+/// received once every response was sent.
+#[cfg(feature = "http")]
+fn serve_http(responses: Vec<String>) -> (String, std::thread::JoinHandle<Vec<String>>) {
+    use std::io::{BufRead, BufReader, Write};
+    use std::net::TcpListener;
+
+    let listener = TcpListener::bind("127.0.0.1:0").expect("Could not bind test server");
+    let url = format!("http://{}/Config.toml", listener.local_addr().unwrap());
+
+    let handle = std::thread::spawn(move || {
+        let mut requests = Vec::new();
+
+        for response in responses {
+            let (mut stream, _) = listener.accept().expect("Could not accept connection");
+            let mut reader = BufReader::new(stream.try_clone().unwrap());
+            let mut request = String::new();
+
+            loop {
+                let mut line = String::new();
+                reader.read_line(&mut line).expect("Could not read request");
+                if line.trim().is_empty() {
+                    break;
+                }
+                request.push_str(&line);
+            }
+            requests.push(request);
+
+            stream
+                .write_all(response.as_bytes())
+                .expect("Could not write response");
+        }
+        requests
+    });
+
+    (url, handle)
+}
+
+/// Create an empty directory unique to the calling test, returning its path.
+fn test_dir(name: &str) -> PathBuf {
+    let dir = std::env::temp_dir().join(format!("graze-{}-{name}", std::process::id()));
+    let _ = fs::remove_dir_all(&dir);
+    fs::create_dir_all(&dir).expect("Could not create test directory");
+    dir
+}
+
 #[test]
 fn load_or_write_default() {
-    let config = crate::load_or_write_default(
-        "Config1.toml",
-        |s| toml::from_str(s),
-        |c| toml::to_string(c).unwrap(),
-        Config::default,
-    );
+    let path = test_dir("load_or_write_default").join("Config.toml");
+
+    let config =
+        crate::load_or_write_default(&path, |s| toml::from_str(s), to_toml, Config::default);
+    assert!(config.is_ok());
+}
+
+#[test]
+fn load_or_write_default_leaves_no_temp_files() {
+    let dir = test_dir("load_or_write_default_leaves_no_temp_files");
+    let path = dir.join("Config.toml");
+
+    let config =
+        crate::load_or_write_default(&path, |s| toml::from_str(s), to_toml, Config::default);
     assert!(config.is_ok());
+    assert!(path.exists());
+
+    let entries: Vec<_> = fs::read_dir(&dir)
+        .expect("Could not read test directory")
+        .map(|e| e.expect("Could not read directory entry").file_name())
+        .collect();
+    assert_eq!(entries, ["Config.toml"]);
 }
 
 #[test]
 fn load_or_default() {
-    let config = crate::load_or_default("Config2.toml", |s| toml::from_str(s), Config::default);
+    let path = test_dir("load_or_default").join("Config.toml");
+
+    let config = crate::load_or_default(&path, |s| toml::from_str(s), Config::default);
     assert!(config.is_ok());
 }
 
 #[test]
 fn load() {
+    let path = test_dir("load").join("Config.toml");
+
     let content = toml::to_string(&Config::default()).expect("Could not convert config to string");
-    fs::write("Config3.toml", content).expect("Could not write default config to path");
+    fs::write(&path, content).expect("Could not write default config to path");
+
+    let config: Result<Config, _> = crate::load_from_path(&path, |s| toml::from_str(s));
+    assert!(config.is_ok());
+}
+
+#[test]
+fn save_to_path_round_trip() {
+    let path = test_dir("save_to_path_round_trip").join("Config.toml");
+    let config = Config { range: 42 };
+
+    crate::save_to_path(&path, &config, toml::to_string).expect("Could not save config");
+
+    let loaded: Config =
+        crate::load_from_path(&path, |s| toml::from_str(s)).expect("Could not load config");
+    assert_eq!(loaded, config);
+}
+
+#[test]
+fn error_source_is_io_error() {
+    let file = test_dir("error_source_is_io_error").join("File");
+    fs::write(&file, "").expect("Could not write file");
+    let path = file.join("Config.toml");
+
+    let err = crate::load_from_path::<_, Config, _, _>(&path, |s| toml::from_str(s))
+        .expect_err("Loading a path below a file should fail");
+    let err: Box<dyn Error> = Box::new(err);
+
+    let source = err.source().expect("Io errors should have a source");
+    assert!(source.downcast_ref::<io::Error>().is_some());
+}
+
+#[test]
+fn io_error_displays_path() {
+    let file = test_dir("io_error_displays_path").join("File");
+    fs::write(&file, "").expect("Could not write file");
+    let path = file.join("Config.toml");
+
+    let err = crate::load_from_path::<_, Config, _, _>(&path, |s| toml::from_str(s))
+        .expect_err("Loading a path below a file should fail");
+
+    assert!(matches!(err, ConfigurationError::Io { path: Some(ref p), .. } if *p == path));
+    assert!(err.to_string().contains(&format!("'{}'", path.display())));
+}
+
+#[test]
+fn error_source_is_deserialize_error() {
+    let path = test_dir("error_source_is_deserialize_error").join("Config.toml");
+    fs::write(&path, "range = ").expect("Could not write config to path");
+
+    let err = crate::load_from_path::<_, Config, _, _>(&path, |s| toml::from_str(s))
+        .expect_err("Loading an invalid file should fail");
+
+    let source = err
+        .source()
+        .expect("Deserialize errors should have a source");
+    assert!(source.downcast_ref::<toml::de::Error>().is_some());
+}
+
+#[test]
+fn load_missing_file_is_not_found() {
+    let path = test_dir("load_missing_file_is_not_found").join("Missing.toml");
+
+    let err = crate::load_from_path::<_, Config, _, _>(&path, |s| toml::from_str(s))
+        .expect_err("Loading a missing file should fail");
+    assert!(matches!(err, ConfigurationError::NotFound { paths } if paths == [path]));
+}
+
+#[cfg(unix)]
+#[test]
+fn load_unreadable_file_is_io_error() {
+    use std::os::unix::fs::PermissionsExt;
+
+    let path = test_dir("load_unreadable_file_is_io_error").join("Config.toml");
+    fs::write(&path, "range = 1").expect("Could not write config to path");
+    fs::set_permissions(&path, fs::Permissions::from_mode(0o000))
+        .expect("Could not set permissions");
+
+    // Privileged users can read the file regardless of its permissions.
+    if fs::read(&path).is_ok() {
+        return;
+    }
+
+    let err = crate::load_from_path::<_, Config, _, _>(&path, |s| toml::from_str(s))
+        .expect_err("Loading an unreadable file should fail");
+    assert!(
+        matches!(err, ConfigurationError::Io { ref source, .. } if source.kind() == io::ErrorKind::PermissionDenied)
+    );
+}
+
+#[cfg(feature = "tokio")]
+#[tokio::test]
+async fn load_or_write_default_async() {
+    let path = test_dir("load_or_write_default_async").join("Config.toml");
+    let default = || Config { range: 7 };
+
+    let written =
+        crate::load_or_write_default_async(&path, |s| toml::from_str(s), to_toml, default)
+            .await
+            .expect("Could not write default config");
+
+    let loaded: Config = crate::load_from_path_async(&path, |s| toml::from_str(s))
+        .await
+        .expect("Could not load config");
+    assert_eq!(loaded, written);
+}
+
+#[cfg(feature = "tokio")]
+#[tokio::test]
+async fn load_or_default_async() {
+    let path = test_dir("load_or_default_async").join("Config.toml");
+
+    let config = crate::load_or_default_async(&path, |s| toml::from_str(s), Config::default)
+        .await
+        .expect("Could not load default config");
+    assert_eq!(config, Config::default());
+    assert!(!path.exists());
+}
+
+#[cfg(feature = "tokio")]
+#[tokio::test]
+async fn load_from_path_async_rejects_directory() {
+    let dir = test_dir("load_from_path_async_rejects_directory");
+
+    let err = crate::load_from_path_async::<_, Config, _, _>(&dir, |s| toml::from_str(s))
+        .await
+        .expect_err("Loading a directory should fail");
+    assert!(matches!(err, ConfigurationError::IsDirectory { ref path } if *path == dir));
+
+    let err = crate::load_or_default_async(&dir, |s| toml::from_str(s), Config::default)
+        .await
+        .expect_err("Loading a directory should fail");
+    assert!(matches!(err, ConfigurationError::IsDirectory { ref path } if *path == dir));
+
+    let err =
+        crate::load_or_write_default_async(&dir, |s| toml::from_str(s), to_toml, Config::default)
+            .await
+            .expect_err("Loading a directory should fail");
+    assert!(matches!(err, ConfigurationError::IsDirectory { ref path } if *path == dir));
+}
+
+#[test]
+fn load_from_first_existing() {
+    let dir = test_dir("load_from_first_existing");
+    let paths = [
+        dir.join("First.toml"),
+        dir.join("Second.toml"),
+        dir.join("Third.toml"),
+    ];
+    fs::write(&paths[1], "range = 2").expect("Could not write config to path");
+
+    let config: Config = crate::load_from_first_existing(&paths, |s| toml::from_str(s))
+        .expect("Could not load config");
+    assert_eq!(config, Config { range: 2 });
+}
+
+#[test]
+fn load_from_first_existing_lists_tried_paths() {
+    let dir = test_dir("load_from_first_existing_lists_tried_paths");
+    let paths = [dir.join("First.toml"), dir.join("Second.toml")];
+
+    let err = crate::load_from_first_existing::<_, _, Config, _, _>(&paths, |s| toml::from_str(s))
+        .expect_err("Loading without any existing candidate should fail");
+    assert!(matches!(err, ConfigurationError::NotFound { paths: tried } if tried == paths));
+}
+
+#[test]
+fn load_from_first_existing_stops_on_invalid_candidate() {
+    let dir = test_dir("load_from_first_existing_stops_on_invalid_candidate");
+    let paths = [dir.join("First.toml"), dir.join("Second.toml")];
+    fs::write(&paths[0], "range = ").expect("Could not write config to path");
+    fs::write(&paths[1], "range = 2").expect("Could not write config to path");
+
+    let err = crate::load_from_first_existing::<_, _, Config, _, _>(&paths, |s| toml::from_str(s))
+        .expect_err("An invalid candidate should not fall through");
+    assert!(matches!(err, ConfigurationError::Deserialize(_)));
+}
+
+#[test]
+fn load_with_overrides() {
+    let path = test_dir("load_with_overrides").join("Config.toml");
+    fs::write(&path, "range = 1").expect("Could not write config to path");
+    std::env::set_var("GRAZE_TEST_RANGE", "5");
+
+    let config: Config = crate::load_with_overrides(
+        &path,
+        |s| toml::from_str(s),
+        |c: &mut Config| {
+            if let Ok(range) = std::env::var("GRAZE_TEST_RANGE") {
+                c.range = range.parse().map_err(serde::de::Error::custom)?;
+            }
+            Ok(())
+        },
+    )
+    .expect("Could not load config");
+    assert_eq!(config, Config { range: 5 });
+}
+
+#[test]
+fn load_or_write_default_creates_parent_dirs() {
+    let path = test_dir("load_or_write_default_creates_parent_dirs")
+        .join("config")
+        .join("nested")
+        .join("Config.toml");
+
+    let config =
+        crate::load_or_write_default(&path, |s| toml::from_str(s), to_toml, Config::default);
+    assert!(config.is_ok());
+    assert!(path.is_file());
+}
+
+#[test]
+fn load_from_reader() {
+    let reader = Cursor::new(b"range = 3".as_slice());
+
+    let config: Config =
+        crate::load_from_reader(reader, |s| toml::from_str(s)).expect("Could not load config");
+    assert_eq!(config, Config { range: 3 });
+}
+
+#[test]
+fn load_from_reader_rejects_invalid_utf8() {
+    let reader = Cursor::new(b"range = \xff".as_slice());
+
+    let err = crate::load_from_reader::<_, Config, _, _>(reader, |s| toml::from_str(s))
+        .expect_err("Invalid UTF-8 should fail");
+    assert!(
+        matches!(err, ConfigurationError::Io { ref source, .. } if source.kind() == io::ErrorKind::InvalidData)
+    );
+}
+
+#[test]
+fn load_from_str() {
+    let config: Config =
+        crate::load_from_str("range = 4", |s| toml::from_str(s)).expect("Could not load config");
+    assert_eq!(config, Config { range: 4 });
+
+    let err = crate::load_from_str::<Config, _, _>("range = ", |s| toml::from_str(s))
+        .expect_err("Invalid content should fail");
+    assert!(matches!(err, ConfigurationError::Deserialize(_)));
+}
+
+/// Load `Base.toml` merged with `Overlay.toml` from `dir`, summing their ranges.
+fn load_summed(dir: &Path) -> crate::Result<Config, toml::de::Error> {
+    crate::load_merged(
+        dir.join("Base.toml"),
+        dir.join("Overlay.toml"),
+        |s| toml::from_str(s),
+        |base: Config, overlay: Config| Config {
+            range: base.range + overlay.range,
+        },
+    )
+}
+
+#[test]
+fn load_merged_with_base_and_overlay() {
+    let dir = test_dir("load_merged_with_base_and_overlay");
+    fs::write(dir.join("Base.toml"), "range = 1").expect("Could not write base config");
+    fs::write(dir.join("Overlay.toml"), "range = 2").expect("Could not write overlay config");
+
+    let config = load_summed(&dir).expect("Could not load config");
+    assert_eq!(config, Config { range: 3 });
+}
+
+#[test]
+fn load_merged_with_base_only() {
+    let dir = test_dir("load_merged_with_base_only");
+    fs::write(dir.join("Base.toml"), "range = 1").expect("Could not write base config");
+
+    let config = load_summed(&dir).expect("Could not load config");
+    assert_eq!(config, Config { range: 1 });
+}
+
+#[test]
+fn load_merged_with_unreadable_overlay() {
+    let dir = test_dir("load_merged_with_unreadable_overlay");
+    fs::write(dir.join("Base.toml"), "range = 1").expect("Could not write base config");
+    fs::create_dir(dir.join("Overlay.toml")).expect("Could not create overlay directory");
+
+    let err = load_summed(&dir).expect_err("An unreadable overlay should fail");
+    assert!(matches!(err, ConfigurationError::IsDirectory { .. }));
+}
+
+#[test]
+fn load_merged_with_overlay_only() {
+    let dir = test_dir("load_merged_with_overlay_only");
+    fs::write(dir.join("Overlay.toml"), "range = 2").expect("Could not write overlay config");
+
+    let err = load_summed(&dir).expect_err("A missing base should fail");
+    assert!(matches!(err, ConfigurationError::NotFound { .. }));
+}
+
+#[test]
+fn load_merged_without_files() {
+    let dir = test_dir("load_merged_without_files");
 
-    let config: Result<Config, _> = crate::load_from_path("Config3.toml", |s| toml::from_str(s));
+    let err = load_summed(&dir).expect_err("A missing base should fail");
+    assert!(
+        matches!(err, ConfigurationError::NotFound { paths } if paths == [dir.join("Base.toml")])
+    );
+}
+
+#[test]
+fn load_or_write_default_with_meta() {
+    let path = test_dir("load_or_write_default_with_meta").join("Config.toml");
+    let load = || {
+        crate::load_or_write_default_with_meta(
+            &path,
+            |s| toml::from_str(s),
+            to_toml,
+            Config::default,
+        )
+    };
+
+    let (_, meta) = load().expect("Could not write default config");
+    assert_eq!(meta.path, path);
+    assert!(meta.wrote_default);
+
+    let (_, meta) = load().expect("Could not load config");
+    assert_eq!(meta.path, path);
+    assert!(!meta.wrote_default);
+}
+
+#[test]
+fn load_from_path_with_mtime() {
+    let path = test_dir("load_from_path_with_mtime").join("Config.toml");
+    fs::write(&path, "range = 2").expect("Could not write config");
+
+    let (config, modified): (Config, _) =
+        crate::load_from_path_with_mtime(&path, |s| toml::from_str(s))
+            .expect("Could not load config");
+    assert_eq!(config, Config { range: 2 });
+
+    let age = std::time::SystemTime::now()
+        .duration_since(modified)
+        .unwrap_or_default();
+    assert!(age < Duration::from_secs(60));
+    assert_eq!(
+        modified,
+        fs::metadata(&path).and_then(|m| m.modified()).unwrap()
+    );
+}
+
+#[test]
+fn load_from_first_existing_with_meta() {
+    let dir = test_dir("load_from_first_existing_with_meta");
+    let paths = [dir.join("First.toml"), dir.join("Second.toml")];
+    fs::write(&paths[1], "range = 2").expect("Could not write config to path");
+
+    let (_, meta): (Config, _) =
+        crate::load_from_first_existing_with_meta(&paths, |s| toml::from_str(s))
+            .expect("Could not load config");
+    assert_eq!(meta.path, paths[1]);
+    assert!(!meta.wrote_default);
+}
+
+#[cfg(unix)]
+#[test]
+fn load_or_write_default_with_mode() {
+    use std::os::unix::fs::PermissionsExt;
+
+    let path = test_dir("load_or_write_default_with_mode").join("Config.toml");
+
+    let config = crate::load_or_write_default_with_mode(
+        &path,
+        |s| toml::from_str(s),
+        to_toml,
+        Config::default,
+        crate::DEFAULT_MODE,
+    );
+    assert!(config.is_ok());
+
+    let metadata = fs::metadata(&path).expect("Could not read config metadata");
+    assert_eq!(metadata.permissions().mode() & 0o777, 0o600);
+}
+
+#[cfg(unix)]
+#[test]
+fn save_to_path_keeps_permissions() {
+    use std::os::unix::fs::PermissionsExt;
+
+    let path = test_dir("save_to_path_keeps_permissions").join("Config.toml");
+
+    crate::load_or_write_default_with_mode(
+        &path,
+        |s| toml::from_str(s),
+        to_toml,
+        Config::default,
+        crate::DEFAULT_MODE,
+    )
+    .expect("Could not write default config");
+
+    crate::save_to_path(&path, &Config { range: 5 }, to_toml).expect("Could not save config");
+
+    let metadata = fs::metadata(&path).expect("Could not read config metadata");
+    assert_eq!(metadata.permissions().mode() & 0o777, 0o600);
+    assert_eq!(fs::read_to_string(&path).unwrap(), "range = 5\n");
+}
+
+#[cfg(unix)]
+#[test]
+fn load_or_write_default_with_mode_ignores_planted_temp_files() {
+    use std::os::unix::fs::{symlink, PermissionsExt};
+
+    let dir = test_dir("load_or_write_default_with_mode_ignores_planted_temp_files");
+    let path = dir.join("Config.toml");
+    let victim = dir.join("Victim.txt");
+    let pid = std::process::id();
+
+    let planted = dir.join(format!("Config.toml.tmp-{pid}"));
+    fs::write(&planted, "").expect("Could not plant temp file");
+    fs::set_permissions(&planted, fs::Permissions::from_mode(0o644))
+        .expect("Could not set temp file permissions");
+
+    fs::write(&victim, "untouched").expect("Could not write victim");
+    symlink(&victim, dir.join(format!("Config.toml.tmp-{pid}-1")))
+        .expect("Could not plant symlink");
+
+    let config = crate::load_or_write_default_with_mode(
+        &path,
+        |s| toml::from_str(s),
+        to_toml,
+        || Config { range: 4 },
+        crate::DEFAULT_MODE,
+    )
+    .expect("Could not write default config");
+    assert_eq!(config, Config { range: 4 });
+
+    let metadata = fs::metadata(&path).expect("Could not read config metadata");
+    assert_eq!(metadata.permissions().mode() & 0o777, 0o600);
+    assert_eq!(fs::read_to_string(&path).unwrap(), "range = 4\n");
+    assert_eq!(fs::read_to_string(&victim).unwrap(), "untouched");
+    assert_eq!(fs::read_to_string(&planted).unwrap(), "");
+}
+
+#[cfg(all(windows, feature = "windows-acl"))]
+#[test]
+fn load_or_write_default_with_mode_restricts_acl() {
+    use std::os::windows::ffi::OsStrExt;
+    use std::ptr;
+
+    use windows_sys::Win32::Foundation::{LocalFree, ERROR_SUCCESS};
+    use windows_sys::Win32::Security::Authorization::{GetNamedSecurityInfoW, SE_FILE_OBJECT};
+    use windows_sys::Win32::Security::{
+        GetSecurityDescriptorControl, ACL, DACL_SECURITY_INFORMATION, PSECURITY_DESCRIPTOR,
+        SE_DACL_PROTECTED,
+    };
+
+    let path = test_dir("load_or_write_default_with_mode_restricts_acl").join("Config.toml");
+
+    let config = crate::load_or_write_default_with_mode(
+        &path,
+        |s| toml::from_str(s),
+        to_toml,
+        Config::default,
+        crate::DEFAULT_MODE,
+    );
     assert!(config.is_ok());
+
+    let wide: Vec<u16> = path.as_os_str().encode_wide().chain([0]).collect();
+    let mut dacl: *mut ACL = ptr::null_mut();
+    let mut descriptor: PSECURITY_DESCRIPTOR = ptr::null_mut();
+
+    // SAFETY: `wide` is nul-terminated, and `descriptor` is freed once `dacl` is no longer used.
+    unsafe {
+        let result = GetNamedSecurityInfoW(
+            wide.as_ptr(),
+            SE_FILE_OBJECT,
+            DACL_SECURITY_INFORMATION,
+            ptr::null_mut(),
+            ptr::null_mut(),
+            &mut dacl,
+            ptr::null_mut(),
+            &mut descriptor,
+        );
+        assert_eq!(result, ERROR_SUCCESS);
+
+        let (mut control, mut revision) = (0, 0);
+        GetSecurityDescriptorControl(descriptor, &mut control, &mut revision);
+        let ace_count = (*dacl).AceCount;
+        LocalFree(descriptor);
+
+        assert_eq!(ace_count, 1);
+        assert_ne!(control & SE_DACL_PROTECTED, 0);
+    }
+}
+
+#[cfg(feature = "watch")]
+#[test]
+fn watch_config() {
+    let path = test_dir("watch_config").join("Config.toml");
+    let watcher = crate::watch_config(&path, |s| toml::from_str::<Config>(s))
+        .expect("Could not watch config");
+    let timeout = Duration::from_secs(5);
+
+    fs::write(&path, "range = 1").expect("Could not write config to path");
+    let config = watcher
+        .recv_timeout(timeout)
+        .expect("The config should have been reloaded");
+    assert_eq!(
+        config.expect("Could not reload config"),
+        Config { range: 1 }
+    );
+
+    fs::write(&path, "range = 2").expect("Could not write config to path");
+    let config = watcher
+        .recv_timeout(timeout)
+        .expect("The config should have been reloaded");
+    assert_eq!(
+        config.expect("Could not reload config"),
+        Config { range: 2 }
+    );
+
+    assert_eq!(watcher.latest(), Some(Config { range: 2 }));
+}
+
+#[test]
+fn config_loader_path() {
+    let path = test_dir("config_loader_path").join("Config.toml");
+    fs::write(&path, "range = 1").expect("Could not write config to path");
+
+    let config: Config = ConfigLoader::new(|s| toml::from_str(s))
+        .path(&path)
+        .load()
+        .expect("Could not load config");
+    assert_eq!(config, Config { range: 1 });
+}
+
+#[test]
+fn config_loader_missing_path() {
+    let path = test_dir("config_loader_missing_path").join("Config.toml");
+
+    let err = ConfigLoader::<Config, _>::new(|s| toml::from_str(s))
+        .path(&path)
+        .load()
+        .expect_err("Loading a missing file without a default should fail");
+    assert!(matches!(err, ConfigurationError::NotFound { paths } if paths == [path]));
+}
+
+#[test]
+fn config_loader_candidates() {
+    let dir = test_dir("config_loader_candidates");
+    fs::write(dir.join("Second.toml"), "range = 2").expect("Could not write config to path");
+
+    let config = ConfigLoader::new(|s| toml::from_str(s))
+        .path(dir.join("First.toml"))
+        .path(dir.join("Second.toml"))
+        .or_default(Config::default)
+        .load()
+        .expect("Could not load config");
+    assert_eq!(config, Config { range: 2 });
+}
+
+#[test]
+fn config_loader_or_default() {
+    let path = test_dir("config_loader_or_default").join("Config.toml");
+
+    let config = ConfigLoader::new(|s| toml::from_str(s))
+        .path(&path)
+        .or_default(|| Config { range: 3 })
+        .load()
+        .expect("Could not load default config");
+    assert_eq!(config, Config { range: 3 });
+    assert!(!path.exists());
+}
+
+#[test]
+fn config_loader_write_if_missing() {
+    let path = test_dir("config_loader_write_if_missing").join("Config.toml");
+
+    let config = ConfigLoader::new(|s| toml::from_str(s))
+        .path(&path)
+        .or_default(|| Config { range: 4 })
+        .write_if_missing(to_toml)
+        .load()
+        .expect("Could not write default config");
+    assert_eq!(config, Config { range: 4 });
+
+    let loaded: Config =
+        crate::load_from_path(&path, |s| toml::from_str(s)).expect("Could not load config");
+    assert_eq!(loaded, config);
+}
+
+#[test]
+fn load_or_default_verbose() {
+    let path = test_dir("load_or_default_verbose").join("Config.toml");
+    let load = || crate::load_or_default_verbose(&path, |s| toml::from_str(s), Config::default);
+
+    let loaded = load().expect("Could not load default config");
+    assert_eq!(loaded, Loaded::Default(Config::default()));
+
+    fs::write(&path, "range = 5").expect("Could not write config to path");
+    let loaded = load().expect("Could not load config");
+    assert_eq!(loaded, Loaded::FromFile(Config { range: 5 }));
+}
+
+/// Check that the range of a configuration is below 10.
+fn validate_range(config: &Config) -> Result<(), toml::de::Error> {
+    if config.range >= 10 {
+        return Err(serde::de::Error::custom("range must be below 10"));
+    }
+    Ok(())
+}
+
+#[test]
+fn load_validated() {
+    let path = test_dir("load_validated").join("Config.toml");
+
+    fs::write(&path, "range = 5").expect("Could not write config to path");
+    let config = crate::load_validated(&path, |s| toml::from_str(s), validate_range)
+        .expect("A valid config should load");
+    assert_eq!(config, Config { range: 5 });
+
+    fs::write(&path, "range = 50").expect("Could not write config to path");
+    let err = crate::load_validated(&path, |s| toml::from_str(s), validate_range)
+        .expect_err("An invalid config should fail");
+    assert!(matches!(err, ConfigurationError::Deserialize(_)));
+}
+
+#[test]
+fn load_or_default_validated_skips_default() {
+    let path = test_dir("load_or_default_validated_skips_default").join("Config.toml");
+
+    let config = crate::load_or_default_validated(
+        &path,
+        |s| toml::from_str(s),
+        || Config { range: 50 },
+        validate_range,
+    )
+    .expect("The default should not be validated");
+    assert_eq!(config, Config { range: 50 });
+}
+
+#[test]
+fn save_to_path_with_backup() {
+    let dir = test_dir("save_to_path_with_backup");
+    let path = dir.join("Config.toml");
+    let save = |config: &Config| {
+        crate::save_to_path_with_backup(
+            &path,
+            config,
+            toml::to_string,
+            crate::DEFAULT_BACKUP_SUFFIX,
+        )
+    };
+
+    save(&Config { range: 1 }).expect("Could not save config");
+    assert!(!dir.join("Config.toml.bak").exists());
+
+    save(&Config { range: 2 }).expect("Could not save config");
+
+    let backup: Config = crate::load_from_path(dir.join("Config.toml.bak"), |s| toml::from_str(s))
+        .expect("Could not load backup");
+    assert_eq!(backup, Config { range: 1 });
+
+    let current: Config =
+        crate::load_from_path(&path, |s| toml::from_str(s)).expect("Could not load config");
+    assert_eq!(current, Config { range: 2 });
+}
+
+#[test]
+fn load_from_path_hashed() {
+    let path = test_dir("load_from_path_hashed").join("Config.toml");
+    fs::write(&path, "range = 1").expect("Could not write config to path");
+
+    let (config, hash): (Config, _) =
+        crate::load_from_path_hashed(&path, |s| toml::from_str(s)).expect("Could not load config");
+    assert_eq!(config, Config { range: 1 });
+    assert!(crate::file_matches_hash(&path, hash).expect("Could not hash config"));
+
+    fs::write(&path, "range = 2").expect("Could not write config to path");
+    assert!(!crate::file_matches_hash(&path, hash).expect("Could not hash config"));
+}
+
+#[test]
+fn load_or_write_default_dry_run() {
+    let path = test_dir("load_or_write_default_dry_run").join("Config.toml");
+
+    let (config, preview) = crate::load_or_write_default_dry_run(
+        &path,
+        |s| toml::from_str(s),
+        to_toml,
+        || Config { range: 6 },
+    )
+    .expect("Could not load default config");
+    assert_eq!(config, Config { range: 6 });
+    assert!(!path.exists());
+
+    let preview = preview.expect("A missing file should produce a preview");
+    assert_eq!(preview, b"range = 6\n");
+}
+
+#[test]
+fn configuration_error_deserialize() {
+    let err = toml::from_str::<Config>("range = ").expect_err("Invalid content should fail");
+    let message = err.to_string();
+
+    let err = ConfigurationError::deserialize(err);
+    assert!(matches!(err, ConfigurationError::Deserialize(ref e) if e.to_string() == message));
+}
+
+#[test]
+fn configuration_error_map_deserialize() {
+    let err = crate::load_from_str::<Config, _, _>("range = ", |s| toml::from_str(s))
+        .expect_err("Invalid content should fail");
+    let message = err.to_string();
+
+    let err: ConfigurationError<String> = err.map_deserialize(|e| e.to_string());
+    assert!(matches!(err, ConfigurationError::Deserialize(_)));
+    assert_eq!(err.to_string(), message);
+
+    let err = ConfigurationError::<toml::de::Error>::from(io::Error::from(io::ErrorKind::Other))
+        .map_deserialize(|e| e.to_string());
+    assert!(matches!(err, ConfigurationError::Io { .. }));
+}
+
+#[cfg(feature = "dirs")]
+#[test]
+fn default_config_path() {
+    let path = crate::default_config_path("com", "Graze", "grazetest", "Config.toml")
+        .expect("Could not find a home directory");
+
+    assert!(path.ends_with("Config.toml"));
+    assert!(path.to_string_lossy().contains("grazetest"));
+}
+
+#[test]
+fn read_with_retry_recovers_from_transient_errors() {
+    let mut failures = 2;
+
+    let content: crate::Result<_, toml::de::Error> =
+        crate::retry::read_with_retry(3, Duration::ZERO, || {
+            if failures > 0 {
+                failures -= 1;
+                return Err(io::Error::from(io::ErrorKind::Interrupted).into());
+            }
+            Ok("range = 1".to_string())
+        });
+    assert_eq!(content.expect("The read should be retried"), "range = 1");
+    assert_eq!(failures, 0);
+}
+
+#[test]
+fn read_with_retry_gives_up() {
+    let mut attempts = 0;
+
+    let err = crate::retry::read_with_retry::<toml::de::Error, _>(2, Duration::ZERO, || {
+        attempts += 1;
+        Err(io::Error::from(io::ErrorKind::WouldBlock).into())
+    })
+    .expect_err("The read should fail once the retries are exhausted");
+    assert!(
+        matches!(err, ConfigurationError::Io { ref source, .. } if source.kind() == io::ErrorKind::WouldBlock)
+    );
+    assert_eq!(attempts, 3);
+}
+
+#[test]
+fn load_from_path_retry_does_not_retry_deserialize_errors() {
+    let path =
+        test_dir("load_from_path_retry_does_not_retry_deserialize_errors").join("Config.toml");
+    fs::write(&path, "range = ").expect("Could not write config to path");
+
+    let mut attempts = 0;
+    let err = crate::load_from_path_retry::<_, Config, _, _>(
+        &path,
+        |s| {
+            attempts += 1;
+            toml::from_str(s)
+        },
+        3,
+        Duration::ZERO,
+    )
+    .expect_err("An invalid config should fail");
+    assert!(matches!(err, ConfigurationError::Deserialize(_)));
+    assert_eq!(attempts, 1);
+}
+
+#[test]
+fn load_or_write_default_keeps_concurrently_created_file() {
+    let path =
+        test_dir("load_or_write_default_keeps_concurrently_created_file").join("Config.toml");
+
+    // The serializer runs after the existence check, so it can simulate another process creating
+    // the file in the meantime.
+    let config = crate::load_or_write_default(
+        &path,
+        |s| toml::from_str(s),
+        |c| {
+            fs::write(&path, "range = 9").expect("Could not write config to path");
+            to_toml(c)
+        },
+        || Config { range: 1 },
+    )
+    .expect("Could not load config");
+    assert_eq!(config, Config { range: 9 });
+
+    let content = fs::read_to_string(&path).expect("Could not read config");
+    assert_eq!(content, "range = 9");
+}
+
+#[cfg(feature = "toml")]
+#[test]
+fn toml_round_trip() {
+    let path = test_dir("toml_round_trip").join("Config.toml");
+
+    let config: Config = crate::load_or_write_default_toml(&path).expect("Could not write config");
+    assert_eq!(config, Config::default());
+
+    crate::save_toml(&path, &Config { range: 2 }).expect("Could not save config");
+    let config: Config = crate::load_toml(&path).expect("Could not load config");
+    assert_eq!(config, Config { range: 2 });
+}
+
+#[cfg(feature = "toml")]
+#[test]
+fn toml_format_round_trip() {
+    use crate::Toml;
+
+    let path = test_dir("toml_format_round_trip").join("Config.toml");
+
+    let config: Config =
+        crate::load_or_write_default_with(&path, Toml).expect("Could not write config");
+    assert_eq!(config, Config::default());
+
+    crate::save_to_path_with(&path, &Config { range: 2 }, Toml).expect("Could not save config");
+    assert_eq!(
+        fs::read_to_string(&path).expect("Could not read config"),
+        "range = 2\n"
+    );
+
+    let config: Config = crate::load_from_path_with(&path, Toml).expect("Could not load config");
+    assert_eq!(config, Config { range: 2 });
+}
+
+#[cfg(feature = "json")]
+#[test]
+fn json_round_trip() {
+    let path = test_dir("json_round_trip").join("Config.json");
+
+    let config: Config =
+        crate::load_or_write_default_json(&path, false).expect("Could not write config");
+    assert_eq!(config, Config::default());
+
+    crate::save_json(&path, &Config { range: 2 }, false).expect("Could not save config");
+    let config: Config = crate::load_json(&path).expect("Could not load config");
+    assert_eq!(config, Config { range: 2 });
+}
+
+#[cfg(feature = "json")]
+#[test]
+fn load_json_strict_rejects_unknown_fields() {
+    let path = test_dir("load_json_strict_rejects_unknown_fields").join("Config.json");
+    fs::write(&path, r#"{"range": 2, "rnage": 3}"#).expect("Could not write config");
+
+    let config: Config = crate::load_json(&path).expect("Could not load config");
+    assert_eq!(config, Config { range: 2 });
+
+    let err = crate::load_json_strict::<_, Config>(&path).expect_err("Unknown field should fail");
+    assert!(matches!(err, ConfigurationError::Deserialize(_)));
+    assert!(err.to_string().contains("rnage"));
+
+    fs::write(&path, r#"{"range": 2}"#).expect("Could not write config");
+    let config: Config = crate::load_json_strict(&path).expect("Could not load config");
+    assert_eq!(config, Config { range: 2 });
+}
+
+#[cfg(feature = "bincode")]
+#[test]
+fn bincode_round_trip() {
+    let path = test_dir("bincode_round_trip").join("Config.bin");
+
+    let config: Config =
+        crate::load_or_write_default_bincode(&path).expect("Could not write config");
+    assert_eq!(config, Config::default());
+
+    crate::save_bincode(&path, &Config { range: 2 }).expect("Could not save config");
+    let config: Config = crate::load_bincode(&path).expect("Could not load config");
+    assert_eq!(config, Config { range: 2 });
+}
+
+#[cfg(feature = "yaml")]
+#[test]
+fn yaml_round_trip() {
+    let path = test_dir("yaml_round_trip").join("Config.yaml");
+
+    let config: Config = crate::load_or_write_default_yaml(&path).expect("Could not write config");
+    assert_eq!(config, Config::default());
+
+    crate::save_yaml(&path, &Config { range: 2 }).expect("Could not save config");
+    let config: Config = crate::load_yaml(&path).expect("Could not load config");
+    assert_eq!(config, Config { range: 2 });
+}
+
+#[cfg(feature = "multi-format")]
+#[test]
+fn load_auto() {
+    let dir = test_dir("load_auto");
+    let files = [
+        ("Config.toml", "range = 1"),
+        ("Config.json", r#"{ "range": 1 }"#),
+        ("Config.yaml", "range: 1"),
+    ];
+
+    for (name, content) in files {
+        let path = dir.join(name);
+        fs::write(&path, content).expect("Could not write config to path");
+
+        let config: Config = crate::load_auto(&path).expect("Could not load config");
+        assert_eq!(config, Config { range: 1 });
+    }
+}
+
+#[cfg(feature = "multi-format")]
+#[test]
+fn load_auto_unsupported_extension() {
+    let path = test_dir("load_auto_unsupported_extension").join("Config.cfg");
+    fs::write(&path, "range = 1").expect("Could not write config to path");
+
+    let err = crate::load_auto::<_, Config>(&path).expect_err("An unknown extension should fail");
+    assert!(matches!(
+        err,
+        ConfigurationError::Deserialize(crate::AutoError::UnsupportedExtension(e)) if e == "cfg"
+    ));
+}
+
+#[test]
+fn load_or_default_directory_is_error() {
+    let path = test_dir("load_or_default_directory_is_error");
+
+    let err = crate::load_or_default(&path, |s| toml::from_str(s), Config::default)
+        .expect_err("A directory should not be replaced by the default");
+    assert!(matches!(err, ConfigurationError::IsDirectory { .. }));
+}
+
+#[test]
+fn load_or_write_default_directory_is_error() {
+    let path = test_dir("load_or_write_default_directory_is_error");
+
+    let err = crate::load_or_write_default(&path, |s| toml::from_str(s), to_toml, Config::default)
+        .expect_err("A directory should not be replaced by the default");
+    assert!(matches!(err, ConfigurationError::IsDirectory { .. }));
+    assert!(path.is_dir());
+}
+
+#[test]
+fn load_or_insert_default() {
+    let path = test_dir("load_or_insert_default").join("Config.toml");
+
+    let mut config = crate::load_or_insert_default(
+        &path,
+        |s| toml::from_str(s).map_err(|e| e.to_string()),
+        |c| toml::to_string(c).map_err(|e| e.to_string()),
+        Config::default,
+    )
+    .expect("Could not load config");
+    assert_eq!(config.path(), path);
+
+    config.get_mut().range = 8;
+    config.save().expect("Could not save config");
+
+    let loaded: Config =
+        crate::load_from_path(&path, |s| toml::from_str(s)).expect("Could not load config");
+    assert_eq!(&loaded, config.get());
+}
+
+#[test]
+fn load_from_path_lenient_strips_bom() {
+    let path = test_dir("load_from_path_lenient_strips_bom").join("Config.toml");
+    fs::write(&path, "\u{FEFF}range = 3").expect("Could not write config");
+
+    let config: Config = crate::load_from_path_lenient(&path, |s| {
+        assert!(!s.starts_with('\u{FEFF}'));
+        toml::from_str(s)
+    })
+    .expect("Could not load config");
+    assert_eq!(config, Config { range: 3 });
+}
+
+#[test]
+fn save_to_path_with_lf_line_endings() {
+    let path = test_dir("save_to_path_with_lf_line_endings").join("Config.toml");
+
+    crate::save_to_path_with_line_endings(
+        &path,
+        &"a = 1\r\nb = 2\n",
+        |c| Ok::<_, io::Error>(c.to_string()),
+        LineEnding::Lf,
+    )
+    .expect("Could not save config");
+    assert_eq!(
+        fs::read(&path).expect("Could not read config"),
+        b"a = 1\nb = 2\n"
+    );
+}
+
+#[test]
+fn save_to_path_with_crlf_line_endings() {
+    let path = test_dir("save_to_path_with_crlf_line_endings").join("Config.toml");
+
+    crate::save_to_path_with_line_endings(
+        &path,
+        &"a = 1\r\nb = 2\n",
+        |c| Ok::<_, io::Error>(c.to_string()),
+        LineEnding::Crlf,
+    )
+    .expect("Could not save config");
+    assert_eq!(
+        fs::read(&path).expect("Could not read config"),
+        b"a = 1\r\nb = 2\r\n"
+    );
+}
+
+#[test]
+fn load_or_write_default_trailing_newline() {
+    let dir = test_dir("load_or_write_default_trailing_newline");
+
+    for (name, serialized, written) in [
+        ("None.toml", "range = 0", "range = 0\n"),
+        ("One.toml", "range = 0\n", "range = 0\n"),
+        ("Several.toml", "range = 0\n\n\n", "range = 0\n"),
+        ("Crlf.toml", "range = 0\r\n", "range = 0\r\n"),
+    ] {
+        let path = dir.join(name);
+        let config = crate::load_or_write_default_trailing_newline(
+            &path,
+            |s| toml::from_str(s),
+            |_| Ok(serialized),
+            Config::default,
+        )
+        .expect("Could not write config");
+
+        assert_eq!(config, Config::default());
+        assert_eq!(
+            fs::read_to_string(&path).expect("Could not read config"),
+            written
+        );
+    }
+}
+
+#[test]
+fn load_or_write_template_writes_template() {
+    #[derive(Deserialize, Default, PartialEq, Debug)]
+    struct DeserializeOnly {
+        range: usize,
+    }
+
+    let path = test_dir("load_or_write_template_writes_template").join("Config.toml");
+    let template = "# The range of the thing\nrange = 0\n";
+
+    let config: DeserializeOnly =
+        crate::load_or_write_template(&path, |s| toml::from_str(s), template, Default::default)
+            .expect("Could not load config");
+    assert_eq!(config, DeserializeOnly::default());
+    assert_eq!(
+        fs::read_to_string(&path).expect("Could not read config"),
+        template
+    );
+
+    fs::write(&path, "range = 4").expect("Could not write config");
+
+    let config: DeserializeOnly =
+        crate::load_or_write_template(&path, |s| toml::from_str(s), template, Default::default)
+            .expect("Could not load config");
+    assert_eq!(config, DeserializeOnly { range: 4 });
+}
+
+#[test]
+fn load_dir_loads_files_in_order() {
+    let dir = test_dir("load_dir_loads_files_in_order");
+    fs::write(dir.join("b.toml"), "range = 2").expect("Could not write config");
+    fs::write(dir.join("c.toml"), "range = 3").expect("Could not write config");
+    fs::write(dir.join("a.toml"), "range = 1").expect("Could not write config");
+    fs::create_dir(dir.join("subdir")).expect("Could not create directory");
+
+    let configs: Vec<Config> =
+        crate::load_dir(&dir, |s| toml::from_str(s)).expect("Could not load configs");
+    assert_eq!(
+        configs,
+        [
+            Config { range: 1 },
+            Config { range: 2 },
+            Config { range: 3 }
+        ]
+    );
+}
+
+#[test]
+fn load_dir_reports_incorrect_file() {
+    let dir = test_dir("load_dir_reports_incorrect_file");
+    fs::write(dir.join("a.toml"), "range = 1").expect("Could not write config");
+    fs::write(dir.join("b.toml"), "range = ").expect("Could not write config");
+
+    let err = crate::load_dir::<_, Config, _, _>(&dir, |s| toml::from_str(s))
+        .expect_err("Incorrect content should fail");
+    assert!(
+        matches!(err, ConfigurationError::DeserializeFile { ref path, .. } if *path == dir.join("b.toml"))
+    );
+    assert!(err.to_string().contains("b.toml"));
+}
+
+#[test]
+fn load_dir_with_extensions_skips_other_files() {
+    let dir = test_dir("load_dir_with_extensions_skips_other_files");
+    fs::write(dir.join("a.toml"), "range = 1").expect("Could not write config");
+    fs::write(dir.join("b.json"), "{}").expect("Could not write file");
+    fs::write(dir.join("README.md"), "# Fragments").expect("Could not write file");
+    fs::write(dir.join(".gitkeep"), "").expect("Could not write file");
+    fs::write(dir.join(".hidden.toml"), "range = ").expect("Could not write file");
+    fs::create_dir(dir.join("nested.toml")).expect("Could not create directory");
+
+    let configs: Vec<Config> =
+        crate::load_dir_with_extensions(&dir, &["toml"], |s| toml::from_str(s))
+            .expect("Could not load configs");
+    assert_eq!(configs, [Config { range: 1 }]);
+}
+
+#[test]
+fn load_dir_merged_folds_files() {
+    use std::collections::BTreeMap;
+
+    #[derive(Deserialize)]
+    struct Entry {
+        name: String,
+        range: usize,
+    }
+
+    let dir = test_dir("load_dir_merged_folds_files");
+    fs::write(dir.join("a.toml"), "name = 'a'\nrange = 1").expect("Could not write config");
+    fs::write(dir.join("b.toml"), "name = 'b'\nrange = 2").expect("Could not write config");
+    fs::write(dir.join("c.toml"), "name = 'c'\nrange = 3").expect("Could not write config");
+
+    let mut order = Vec::new();
+    let merged: BTreeMap<String, usize> = crate::load_dir_merged(
+        &dir,
+        |s| toml::from_str(s),
+        |acc: &mut BTreeMap<_, _>, entry: Entry| {
+            order.push(entry.name.clone());
+            acc.insert(entry.name, entry.range);
+        },
+    )
+    .expect("Could not load configs");
+
+    assert_eq!(order, ["a", "b", "c"]);
+    assert_eq!(
+        merged,
+        BTreeMap::from([("a".into(), 1), ("b".into(), 2), ("c".into(), 3)])
+    );
+}
+
+#[test]
+fn load_from_path_bytes_passes_raw_bytes() {
+    let path = test_dir("load_from_path_bytes_passes_raw_bytes").join("Config.bin");
+    fs::write(&path, [0x03, 0xff, 0x00]).expect("Could not write config");
+
+    let range = crate::load_from_path_bytes(&path, |b| match b {
+        [range, ..] => Ok(*range as usize),
+        [] => Err("The file is empty"),
+    })
+    .expect("Could not load config");
+    assert_eq!(range, 3);
+
+    let err =
+        crate::load_from_path_bytes::<_, usize, &str, _>(path.with_extension("missing"), |_| Ok(0))
+            .expect_err("Loading a missing file should fail");
+    assert!(matches!(err, ConfigurationError::NotFound { .. }));
+}
+
+#[test]
+fn load_or_write_default_bytes_writes_raw_bytes() {
+    let path = test_dir("load_or_write_default_bytes_writes_raw_bytes").join("Config.bin");
+    let deserializer = |b: &[u8]| Ok::<_, io::Error>(b.to_vec());
+
+    let config = crate::load_or_write_default_bytes(
+        &path,
+        deserializer,
+        |c| Ok(c.clone()),
+        || vec![0xff, 0x00],
+    )
+    .expect("Could not write config");
+    assert_eq!(config, [0xff, 0x00]);
+    assert_eq!(
+        fs::read(&path).expect("Could not read config"),
+        [0xff, 0x00]
+    );
+
+    let config =
+        crate::load_or_write_default_bytes(&path, deserializer, |c| Ok(c.clone()), Vec::new)
+            .expect("Could not load config");
+    assert_eq!(config, [0xff, 0x00]);
+}
+
+#[test]
+fn save_if_changed_skips_identical_content() {
+    let path = test_dir("save_if_changed_skips_identical_content").join("Config.toml");
+    let config = Config { range: 3 };
+
+    let written =
+        crate::save_if_changed(&path, &config, toml::to_string).expect("Could not save config");
+    assert!(written);
+
+    let written =
+        crate::save_if_changed(&path, &config, toml::to_string).expect("Could not save config");
+    assert!(!written);
+
+    let written = crate::save_if_changed(&path, &Config { range: 4 }, toml::to_string)
+        .expect("Could not save config");
+    assert!(written);
+    assert_eq!(
+        fs::read_to_string(&path).expect("Could not read config"),
+        "range = 4\n"
+    );
+}
+
+#[test]
+fn persist_replaces_existing_file_atomically() {
+    let dir = test_dir("persist_replaces_existing_file_atomically");
+    let path = dir.join("Config.toml");
+    fs::write(&path, "range = 1").expect("Could not write config to path");
+
+    crate::persist(
+        &path,
+        &Config { range: 2 },
+        toml::to_string,
+        &crate::PersistOptions::default(),
+    )
+    .expect("Could not persist config");
+
+    let config: Config =
+        crate::load_from_path(&path, |s| toml::from_str(s)).expect("Could not load config");
+    assert_eq!(config, Config { range: 2 });
+
+    let entries: Vec<_> = fs::read_dir(&dir)
+        .expect("Could not read test directory")
+        .map(|e| e.expect("Could not read directory entry").file_name())
+        .collect();
+    assert_eq!(entries, ["Config.toml"]);
+}
+
+#[test]
+fn persist_with_backup() {
+    let dir = test_dir("persist_with_backup");
+    let path = dir.join("Config.toml");
+    let options = crate::PersistOptions {
+        backup: true,
+        ..crate::PersistOptions::default()
+    };
+
+    crate::persist(&path, &Config { range: 1 }, toml::to_string, &options)
+        .expect("Could not persist config");
+    assert!(!dir.join("Config.toml.bak").exists());
+
+    crate::persist(&path, &Config { range: 2 }, toml::to_string, &options)
+        .expect("Could not persist config");
+
+    let backup: Config = crate::load_from_path(dir.join("Config.toml.bak"), |s| toml::from_str(s))
+        .expect("Could not load backup");
+    assert_eq!(backup, Config { range: 1 });
+
+    let current: Config =
+        crate::load_from_path(&path, |s| toml::from_str(s)).expect("Could not load config");
+    assert_eq!(current, Config { range: 2 });
+}
+
+#[test]
+fn persist_without_create_dirs_fails_for_missing_parent() {
+    let path = test_dir("persist_without_create_dirs_fails_for_missing_parent")
+        .join("nested")
+        .join("Config.toml");
+    let options = crate::PersistOptions {
+        create_dirs: false,
+        ..crate::PersistOptions::default()
+    };
+
+    let result = crate::persist(&path, &Config { range: 1 }, toml::to_string, &options);
+    assert!(matches!(result, Err(ConfigurationError::Io { .. })));
+
+    crate::persist(
+        &path,
+        &Config { range: 1 },
+        toml::to_string,
+        &crate::PersistOptions::default(),
+    )
+    .expect("Could not persist config");
+    assert!(path.exists());
+}
+
+#[cfg(unix)]
+#[test]
+fn persist_in_place_with_mode() {
+    use std::os::unix::fs::PermissionsExt;
+
+    let path = test_dir("persist_in_place_with_mode").join("Config.toml");
+    let options = crate::PersistOptions {
+        atomic: false,
+        mode: Some(crate::DEFAULT_MODE),
+        ..crate::PersistOptions::default()
+    };
+
+    crate::persist(&path, &Config { range: 1 }, toml::to_string, &options)
+        .expect("Could not persist config");
+
+    let metadata = fs::metadata(&path).expect("Could not read config metadata");
+    assert_eq!(metadata.permissions().mode() & 0o777, 0o600);
+    assert_eq!(
+        fs::read_to_string(&path).expect("Could not read config"),
+        "range = 1\n"
+    );
+}
+
+#[cfg(feature = "lock")]
+#[test]
+fn load_locked_rejects_second_lock() {
+    let path = test_dir("load_locked_rejects_second_lock").join("Config.toml");
+    fs::write(&path, "range = 3").expect("Could not write config");
+
+    let (config, lock): (Config, _) =
+        crate::load_locked(&path, |s| toml::from_str(s)).expect("Could not lock config");
+    assert_eq!(config, Config { range: 3 });
+    assert_eq!(lock.path(), path);
+
+    let err = crate::load_locked::<_, Config, _, _>(&path, |s| toml::from_str(s))
+        .expect_err("Locking a locked file should fail");
+    assert!(matches!(err, ConfigurationError::Locked { path: ref p } if *p == path));
+
+    drop(lock);
+    crate::load_locked::<_, Config, _, _>(&path, |s| toml::from_str(s))
+        .expect("The lock should be released when dropped");
+}
+
+#[test]
+fn load_with_includes_follows_chain() {
+    let dir = test_dir("load_with_includes_follows_chain");
+    fs::create_dir(dir.join("nested")).expect("Could not create directory");
+    fs::write(
+        dir.join("Config.toml"),
+        "# include nested/a.toml\nrange = 1",
+    )
+    .expect("Could not write config");
+    fs::write(dir.join("nested/a.toml"), "# include b.toml\nrange = 2")
+        .expect("Could not write config");
+    fs::write(dir.join("nested/b.toml"), "range = 3").expect("Could not write config");
+
+    let config: Config = crate::load_with_includes(
+        dir.join("Config.toml"),
+        |s| toml::from_str(s),
+        extract_includes,
+        |a: Config, b: Config| Config {
+            range: a.range * 10 + b.range,
+        },
+    )
+    .expect("Could not load config");
+
+    // b.toml is merged into a.toml (23), which is then merged into Config.toml (10 + 23).
+    assert_eq!(config, Config { range: 33 });
+}
+
+#[test]
+fn load_with_includes_detects_cycle() {
+    let dir = test_dir("load_with_includes_detects_cycle");
+    fs::write(dir.join("a.toml"), "# include b.toml\nrange = 1").expect("Could not write config");
+    fs::write(dir.join("b.toml"), "# include a.toml\nrange = 2").expect("Could not write config");
+
+    let err = crate::load_with_includes::<_, Config, _, _, _, _>(
+        dir.join("a.toml"),
+        |s| toml::from_str(s),
+        extract_includes,
+        |a, _| a,
+    )
+    .expect_err("A cycle should fail");
+
+    let expected = [dir.join("a.toml"), dir.join("b.toml"), dir.join("a.toml")];
+    assert!(matches!(err, ConfigurationError::IncludeCycle { ref paths } if *paths == expected));
+}
+
+/// Extract the paths of `# include <path>` lines.
+fn extract_includes(content: &str) -> Vec<String> {
+    content
+        .lines()
+        .filter_map(|line| line.strip_prefix("# include "))
+        .map(str::to_string)
+        .collect()
+}
+
+#[test]
+fn load_from_reader_timeout_times_out() {
+    struct SlowReader;
+
+    impl io::Read for SlowReader {
+        fn read(&mut self, _: &mut [u8]) -> io::Result<usize> {
+            std::thread::sleep(Duration::from_secs(1));
+            Ok(0)
+        }
+    }
+
+    let err = crate::load_from_reader_timeout::<_, Config, _, _>(
+        SlowReader,
+        |s| toml::from_str(s),
+        Duration::from_millis(10),
+    )
+    .expect_err("A slow read should time out");
+    assert!(
+        matches!(err, ConfigurationError::Io { ref source, .. } if source.kind() == io::ErrorKind::TimedOut)
+    );
+
+    let config: Config = crate::load_from_reader_timeout(
+        Cursor::new(b"range = 3"),
+        |s| toml::from_str(s),
+        Duration::from_secs(10),
+    )
+    .expect("Could not load config");
+    assert_eq!(config, Config { range: 3 });
+}
+
+#[test]
+fn diff_serialized_finds_changes() {
+    use crate::LineDiff;
+
+    let old = "[server]\nhost = 'localhost'\nport = 80\n\n[client]\nretries = 3\n";
+    let new = "[server]\nhost = 'example.com'\nport = 80\ntls = true\n\n[client]\n";
+
+    assert_eq!(
+        crate::diff_serialized(old, new),
+        [
+            LineDiff::Changed {
+                old_line: 2,
+                new_line: 2,
+                old: "host = 'localhost'".to_string(),
+                new: "host = 'example.com'".to_string(),
+            },
+            LineDiff::Added {
+                new_line: 4,
+                content: "tls = true".to_string(),
+            },
+            LineDiff::Removed {
+                old_line: 6,
+                content: "retries = 3".to_string(),
+            },
+        ]
+    );
+    assert!(crate::diff_serialized(old, old).is_empty());
+}
+
+#[test]
+fn drift_report_names_changed_fields() {
+    #[derive(Deserialize)]
+    struct Settings {
+        message: String,
+        range: usize,
+    }
+
+    let path = test_dir("drift_report_names_changed_fields").join("Config.toml");
+    fs::write(&path, "message = 'Hello'\nrange = 5").expect("Could not write config");
+
+    let drift = crate::drift_report(
+        &path,
+        |s| toml::from_str(s),
+        || Settings {
+            message: "Hello".to_string(),
+            range: 1,
+        },
+        |config: &Settings, default: &Settings| {
+            let mut changed = Vec::new();
+            if config.message != default.message {
+                changed.push("message".to_string());
+            }
+            if config.range != default.range {
+                changed.push("range".to_string());
+            }
+            changed
+        },
+    )
+    .expect("Could not load config");
+    assert_eq!(drift, ["range"]);
+}
+
+#[test]
+fn load_with_migration_rewrites_content() {
+    let path = test_dir("load_with_migration_rewrites_content").join("Config.toml");
+    fs::write(&path, "version = 1\ndistance = 3\n").expect("Could not write config");
+
+    let migrate = |s: &str| match s.lines().next() {
+        Some("version = 1") => Ok(s
+            .replace("version = 1", "version = 2")
+            .replace("distance =", "range =")),
+        Some("version = 2") => Ok(s.to_string()),
+        _ => Err(String::from("Unknown version")),
+    };
+    let deserializer = |s: &str| toml::from_str::<Config>(s).map_err(|e| e.to_string());
+
+    let config =
+        crate::load_with_migration(&path, deserializer, migrate).expect("Could not load config");
+    assert_eq!(config, Config { range: 3 });
+    assert_eq!(
+        fs::read_to_string(&path).expect("Could not read config"),
+        "version = 1\ndistance = 3\n"
+    );
+
+    let config = crate::load_with_migration_persisted(&path, deserializer, migrate)
+        .expect("Could not load config");
+    assert_eq!(config, Config { range: 3 });
+    assert_eq!(
+        fs::read_to_string(&path).expect("Could not read config"),
+        "version = 2\nrange = 3\n"
+    );
+}
+
+#[test]
+fn load_into_keeps_other_fields() {
+    #[derive(Deserialize, PartialEq, Debug)]
+    struct Cached {
+        range: usize,
+        #[serde(skip)]
+        cache: Vec<usize>,
+    }
+
+    let path = test_dir("load_into_keeps_other_fields").join("Config.toml");
+    fs::write(&path, "range = 3").expect("Could not write config");
+
+    let mut config = Cached {
+        range: 1,
+        cache: vec![1, 2],
+    };
+    crate::load_into(&path, &mut config, |s, config: &mut Cached| {
+        config.range = toml::from_str::<Cached>(s)?.range;
+        Ok::<_, toml::de::Error>(())
+    })
+    .expect("Could not load config");
+
+    assert_eq!(
+        config,
+        Cached {
+            range: 3,
+            cache: vec![1, 2],
+        }
+    );
+}
+
+#[cfg(feature = "gzip")]
+#[test]
+fn gzip_round_trip() {
+    let path = test_dir("gzip_round_trip").join("Config.toml.gz");
+
+    let config: Config = crate::load_or_write_default(
+        &path,
+        |s| toml::from_str(s),
+        to_toml,
+        || Config { range: 3 },
+    )
+    .expect("Could not write config");
+    assert_eq!(config, Config { range: 3 });
+
+    let content = fs::read(&path).expect("Could not read config");
+    assert!(content.starts_with(&[0x1f, 0x8b]));
+
+    let config: Config =
+        crate::load_from_path(&path, |s| toml::from_str(s)).expect("Could not load config");
+    assert_eq!(config, Config { range: 3 });
+}
+
+#[cfg(all(feature = "gzip", feature = "tokio"))]
+#[tokio::test]
+async fn gzip_round_trip_async() {
+    let path = test_dir("gzip_round_trip_async").join("Config.toml.gz");
+
+    let config: Config = crate::load_or_write_default_async(
+        &path,
+        |s| toml::from_str(s),
+        to_toml,
+        || Config { range: 3 },
+    )
+    .await
+    .expect("Could not write config");
+    assert_eq!(config, Config { range: 3 });
+
+    let content = fs::read(&path).expect("Could not read config");
+    assert!(content.starts_with(&[0x1f, 0x8b]));
+
+    let config: Config = crate::load_from_path_async(&path, |s| toml::from_str(s))
+        .await
+        .expect("Could not load config");
+    assert_eq!(config, Config { range: 3 });
+
+    let config = crate::load_or_default_async(&path, |s| toml::from_str(s), Config::default)
+        .await
+        .expect("Could not load config");
+    assert_eq!(config, Config { range: 3 });
+}
+
+#[cfg(all(feature = "gzip", feature = "lock"))]
+#[test]
+fn load_locked_decompresses_gzip() {
+    let path = test_dir("load_locked_decompresses_gzip").join("Config.toml.gz");
+    crate::save_to_path(&path, &Config { range: 3 }, to_toml).expect("Could not save config");
+
+    let (config, _lock): (Config, _) =
+        crate::load_locked(&path, |s| toml::from_str(s)).expect("Could not load config");
+    assert_eq!(config, Config { range: 3 });
+}
+
+#[cfg(feature = "gzip")]
+#[test]
+fn load_from_path_timeout_decompresses_gzip() {
+    let path = test_dir("load_from_path_timeout_decompresses_gzip").join("Config.toml.gz");
+    crate::save_to_path(&path, &Config { range: 3 }, to_toml).expect("Could not save config");
+
+    let config: Config =
+        crate::load_from_path_timeout(&path, |s| toml::from_str(s), Duration::from_secs(5))
+            .expect("Could not load config");
+    assert_eq!(config, Config { range: 3 });
+}
+
+#[cfg(all(feature = "error-snippets", feature = "toml"))]
+#[test]
+fn format_parse_error_shows_line() {
+    let path = test_dir("format_parse_error_shows_line").join("Config.toml");
+    fs::write(&path, "# The range\nrange = 'three\n").expect("Could not write config");
+
+    let content = fs::read_to_string(&path).expect("Could not read config");
+    let err = match crate::load_from_path::<_, Config, _, _>(&path, |s| toml::from_str(s)) {
+        Err(ConfigurationError::Deserialize(err)) => err,
+        result => panic!("Incorrect content should fail to deserialize: {result:?}"),
+    };
+
+    let snippet = crate::format_parse_error(&content, &err);
+    assert!(snippet.starts_with(&err.to_string()));
+    assert!(snippet.contains("2 | range = 'three\n"));
+    assert!(snippet.contains("line 2"));
+}
+
+#[test]
+fn load_from_path_expanded_expands_variables() {
+    let path = test_dir("load_from_path_expanded_expands_variables").join("Config.toml");
+    std::env::set_var("GRAZE_TEST_EXPANDED_RANGE", "3");
+    fs::write(&path, "range = ${GRAZE_TEST_EXPANDED_RANGE}").expect("Could not write config");
+
+    let config: Config =
+        crate::load_from_path_expanded(&path, |s| toml::from_str(s), Expansion::Strict)
+            .expect("Could not load config");
+    assert_eq!(config, Config { range: 3 });
+}
+
+#[test]
+fn load_from_path_expanded_handles_undefined_variables() {
+    let path = test_dir("load_from_path_expanded_handles_undefined_variables").join("Config.toml");
+    fs::write(&path, "${GRAZE_TEST_UNDEFINED}").expect("Could not write config");
+
+    let content = crate::load_from_path_expanded(
+        &path,
+        |s| Ok::<_, io::Error>(s.to_string()),
+        Expansion::Lenient,
+    )
+    .expect("Could not load config");
+    assert_eq!(content, "${GRAZE_TEST_UNDEFINED}");
+
+    let err = crate::load_from_path_expanded(
+        &path,
+        |s| Ok::<_, io::Error>(s.to_string()),
+        Expansion::Strict,
+    )
+    .expect_err("An undefined variable should fail");
+    assert!(
+        matches!(err, ConfigurationError::UndefinedVariable { ref name } if name == "GRAZE_TEST_UNDEFINED")
+    );
+}
+
+#[test]
+fn load_from_path_expanded_unescapes_dollars() {
+    let path = test_dir("load_from_path_expanded_unescapes_dollars").join("Config.toml");
+    fs::write(&path, "$${HOME} costs $5 and $$").expect("Could not write config");
+
+    let content = crate::load_from_path_expanded(
+        &path,
+        |s| Ok::<_, io::Error>(s.to_string()),
+        Expansion::Strict,
+    )
+    .expect("Could not load config");
+    assert_eq!(content, "${HOME} costs $5 and $");
+}
+
+#[test]
+fn load_or_write_default_with_callback_fires_once() {
+    let path = test_dir("load_or_write_default_with_callback_fires_once").join("Config.toml");
+    let mut written = Vec::new();
+
+    for _ in 0..2 {
+        let config: Config = crate::load_or_write_default_with_callback(
+            &path,
+            |s| toml::from_str(s),
+            to_toml,
+            || Config { range: 3 },
+            |p, c| written.push((p.to_path_buf(), c.clone())),
+        )
+        .expect("Could not load config");
+        assert_eq!(config, Config { range: 3 });
+    }
+    assert_eq!(written, [(path, Config { range: 3 })]);
+}
+
+#[test]
+fn load_or_write_default_propagates_serializer_error() {
+    let path = test_dir("load_or_write_default_propagates_serializer_error").join("Config.toml");
+
+    let err = crate::load_or_write_default(
+        &path,
+        |s| toml::from_str(s).map_err(|e| e.to_string()),
+        |_: &Config| Err::<String, _>("Could not serialize".to_string()),
+        Config::default,
+    )
+    .expect_err("A failing serializer should fail");
+    assert!(matches!(err, ConfigurationError::Serialize(ref e) if e == "Could not serialize"));
+    assert!(!path.exists());
+}
+
+#[test]
+fn save_to_path_reports_serialize_error() {
+    let path = test_dir("save_to_path_reports_serialize_error").join("Config.toml");
+    fs::write(&path, "range = 1").expect("Could not write config");
+
+    let err = crate::save_to_path(&path, &Config { range: 2 }, |_| {
+        Err::<String, _>("Could not serialize".to_string())
+    })
+    .expect_err("A failing serializer should fail");
+    assert!(matches!(err, ConfigurationError::Serialize(_)));
+    assert_eq!(
+        err.to_string(),
+        "Could not serialize configuration: Could not serialize"
+    );
+    assert_eq!(fs::read_to_string(&path).unwrap(), "range = 1");
+}
+
+#[test]
+fn write_template_preserves_comments() {
+    let path = test_dir("write_template_preserves_comments")
+        .join("nested")
+        .join("Config.toml");
+    let template = "# The range of the thing\n# Must be positive\nrange = 1 # inline\n";
+
+    assert!(crate::write_template(&path, template).expect("Could not write template"));
+    let written = fs::read_to_string(&path).expect("Could not read config");
+    assert_eq!(written, template);
+    assert!(written.lines().any(|l| l == "# Must be positive"));
+
+    assert!(!crate::write_template(&path, "range = 2").expect("Could not write template"));
+    assert_eq!(fs::read_to_string(&path).unwrap(), template);
+}
+
+#[test]
+fn verify_writable_accepts_writable_directory() {
+    let dir = test_dir("verify_writable_accepts_writable_directory");
+
+    crate::verify_writable(dir.join("Config.toml")).expect("The directory should be writable");
+    assert_eq!(fs::read_dir(&dir).unwrap().count(), 0);
+
+    let err = crate::verify_writable(dir.join("missing").join("Config.toml"))
+        .expect_err("A missing directory should not be writable");
+    assert_eq!(err.kind(), io::ErrorKind::NotFound);
+}
+
+#[test]
+fn verify_writable_ignores_stale_probe() {
+    let dir = test_dir("verify_writable_ignores_stale_probe");
+    let stale = dir.join(format!("Config.toml.tmp-{}", std::process::id()));
+    fs::write(&stale, "stale").expect("Could not write stale probe");
+
+    crate::verify_writable(dir.join("Config.toml")).expect("The directory should be writable");
+    assert_eq!(fs::read_to_string(&stale).unwrap(), "stale");
+    assert_eq!(fs::read_dir(&dir).unwrap().count(), 1);
+}
+
+#[cfg(unix)]
+#[test]
+fn verify_writable_rejects_read_only_directory() {
+    use std::os::unix::fs::PermissionsExt;
+
+    let dir = test_dir("verify_writable_rejects_read_only_directory");
+    fs::set_permissions(&dir, fs::Permissions::from_mode(0o555))
+        .expect("Could not set permissions");
+
+    let result = crate::verify_writable(dir.join("Config.toml"));
+
+    // Privileged users can write to the directory regardless of its permissions.
+    let privileged = fs::write(dir.join("probe"), "").is_ok();
+    fs::set_permissions(&dir, fs::Permissions::from_mode(0o755))
+        .expect("Could not set permissions");
+    if privileged {
+        return;
+    }
+
+    let err = result.expect_err("A read-only directory should not be writable");
+    assert_eq!(err.kind(), io::ErrorKind::PermissionDenied);
+}
+
+#[cfg(feature = "json")]
+#[test]
+fn load_trying_falls_back_to_later_deserializer() {
+    type Deserializer = Box<dyn Fn(&str) -> Result<Config, String>>;
+
+    let deserializers = || {
+        vec![
+            Box::new(|s: &str| toml::from_str(s).map_err(|e| format!("toml: {e}"))) as Deserializer,
+            Box::new(|s: &str| serde_json::from_str(s).map_err(|e| format!("json: {e}"))),
+        ]
+    };
+
+    let dir = test_dir("load_trying_falls_back_to_later_deserializer");
+    let path = dir.join("Config.conf");
+    fs::write(&path, r#"{ "range": 6 }"#).expect("Could not write config");
+
+    let config = crate::load_trying(&path, deserializers()).expect("Could not load config");
+    assert_eq!(config, Config { range: 6 });
+
+    fs::write(&path, "range: 6").expect("Could not write config");
+
+    let err = crate::load_trying(&path, deserializers()).expect_err("Invalid content should fail");
+    assert!(matches!(err, ConfigurationError::Deserialize(ref e) if e.starts_with("json: ")));
+}
+
+#[test]
+fn load_trying_rejects_empty_deserializers() {
+    type Deserializer = Box<dyn Fn(&str) -> Result<Config, toml::de::Error>>;
+
+    let path = test_dir("load_trying_rejects_empty_deserializers").join("Config.toml");
+    fs::write(&path, "range = 6").expect("Could not write config");
+
+    let err = crate::load_trying(&path, Vec::<Deserializer>::new())
+        .expect_err("No deserializers should fail");
+    assert!(
+        matches!(err, ConfigurationError::Io { ref source, .. } if source.kind() == io::ErrorKind::InvalidInput)
+    );
+}
+
+#[test]
+fn configuration_error_into_io_error() {
+    fn load(path: &Path) -> io::Result<Config> {
+        Ok(crate::load_from_path(path, |s| toml::from_str(s))?)
+    }
+
+    let dir = test_dir("configuration_error_into_io_error");
+    let path = dir.join("Config.toml");
+    fs::write(&path, "range = ").expect("Could not write config");
+
+    let err = load(&path).expect_err("Invalid content should fail");
+    assert_eq!(err.kind(), io::ErrorKind::InvalidData);
+    assert!(err
+        .to_string()
+        .starts_with("Configuration file is incorrect: "));
+
+    let err = load(dir.as_path()).expect_err("Loading a directory should fail");
+    assert_eq!(err.kind(), io::ErrorKind::IsADirectory);
+
+    let err = load(&dir.join("Missing.toml")).expect_err("Loading a missing file should fail");
+    assert_eq!(err.kind(), io::ErrorKind::NotFound);
+}
+
+#[test]
+fn load_or_default_on_error() {
+    let dir = test_dir("load_or_default_on_error");
+    let load = |path: &Path| {
+        let mut errors = 0;
+        let config = crate::load_or_default_on_error(
+            path,
+            |s| toml::from_str(s),
+            || Config { range: 7 },
+            |_| errors += 1,
+        );
+        (config, errors)
+    };
+
+    assert_eq!(load(&dir.join("Missing.toml")), (Config { range: 7 }, 0));
+
+    let corrupt = dir.join("Corrupt.toml");
+    fs::write(&corrupt, "range = ").expect("Could not write config");
+    assert_eq!(load(&corrupt), (Config { range: 7 }, 1));
+
+    let valid = dir.join("Valid.toml");
+    fs::write(&valid, "range = 2").expect("Could not write config");
+    assert_eq!(load(&valid), (Config { range: 2 }, 0));
+}
+
+#[test]
+fn load_with_policy_error() {
+    let path = test_dir("load_with_policy_error").join("Config.toml");
+
+    let err = crate::load_with_policy::<_, Config, _, _>(
+        &path,
+        |s| toml::from_str(s),
+        MissingPolicy::Error,
+    )
+    .expect_err("A missing file should fail");
+    assert!(matches!(err, ConfigurationError::NotFound { .. }));
+}
+
+#[test]
+fn load_with_policy_default() {
+    let path = test_dir("load_with_policy_default").join("Config.toml");
+
+    let config = crate::load_with_policy(
+        &path,
+        |s| toml::from_str(s),
+        MissingPolicy::default(|| Config { range: 5 }),
+    )
+    .expect("Could not load config");
+    assert_eq!(config, Config { range: 5 });
+    assert!(!path.exists());
+}
+
+#[test]
+fn load_with_policy_write_default() {
+    let path = test_dir("load_with_policy_write_default").join("Config.toml");
+    let policy = || MissingPolicy::write_default(to_toml, || Config { range: 5 });
+
+    let config = crate::load_with_policy(&path, |s| toml::from_str(s), policy())
+        .expect("Could not load config");
+    assert_eq!(config, Config { range: 5 });
+    assert_eq!(
+        fs::read_to_string(&path).expect("Could not read config"),
+        "range = 5\n"
+    );
+
+    fs::write(&path, "range = 6").expect("Could not write config");
+    let config = crate::load_with_policy(&path, |s| toml::from_str(s), policy())
+        .expect("Could not load config");
+    assert_eq!(config, Config { range: 6 });
+}
+
+#[test]
+fn load_or_value_uses_owned_default() {
+    #[derive(Deserialize, PartialEq, Debug)]
+    struct Named {
+        name: String,
+    }
+
+    let path = test_dir("load_or_value_uses_owned_default").join("Config.toml");
+    let default = Named {
+        name: "default".to_string(),
+    };
+
+    let config = crate::load_or_value(&path, |s| toml::from_str(s), default)
+        .expect("Could not load default config");
+    assert_eq!(config.name, "default");
+
+    fs::write(&path, "name = 'file'").expect("Could not write config");
+    let default = Named {
+        name: "default".to_string(),
+    };
+
+    let config =
+        crate::load_or_value(&path, |s| toml::from_str(s), default).expect("Could not load config");
+    assert_eq!(config.name, "file");
+}
+
+#[test]
+fn cached_config_reloads_only_when_modified() {
+    use std::cell::Cell;
+    use std::rc::Rc;
+    use std::time::SystemTime;
+
+    let path = test_dir("cached_config_reloads_only_when_modified").join("Config.toml");
+    fs::write(&path, "range = 1").expect("Could not write config");
+
+    let parses = Rc::new(Cell::new(0));
+    let counter = Rc::clone(&parses);
+
+    let mut cached = crate::CachedConfig::load(&path, move |s| {
+        counter.set(counter.get() + 1);
+        toml::from_str::<Config>(s)
+    })
+    .expect("Could not load config");
+
+    for _ in 0..2 {
+        let (config, err) = cached.get();
+        assert_eq!(config, &Config { range: 1 });
+        assert!(err.is_none());
+    }
+    assert_eq!(parses.get(), 1);
+
+    fs::write(&path, "range = 2").expect("Could not write config");
+    fs::File::options()
+        .write(true)
+        .open(&path)
+        .and_then(|file| file.set_modified(SystemTime::now() + Duration::from_secs(60)))
+        .expect("Could not touch config");
+
+    let (config, err) = cached.get();
+    assert_eq!(config, &Config { range: 2 });
+    assert!(err.is_none());
+    assert_eq!(parses.get(), 2);
+
+    fs::remove_file(&path).expect("Could not remove config");
+
+    let (config, err) = cached.get();
+    assert_eq!(config, &Config { range: 2 });
+    assert!(matches!(err, Some(ConfigurationError::NotFound { .. })));
+}
+
+/// An in-memory [crate::ConfigFs], for testing loaders without touching the real filesystem.
+#[derive(Default)]
+struct MemoryFs {
+    files: std::cell::RefCell<std::collections::HashMap<PathBuf, String>>,
+}
+
+impl crate::ConfigFs for MemoryFs {
+    fn read(&self, path: &Path) -> io::Result<String> {
+        self.files
+            .borrow()
+            .get(path)
+            .cloned()
+            .ok_or_else(|| io::ErrorKind::NotFound.into())
+    }
+
+    fn write(&self, path: &Path, contents: &[u8]) -> io::Result<()> {
+        let contents = String::from_utf8(contents.to_vec())
+            .map_err(|err| io::Error::new(io::ErrorKind::InvalidData, err))?;
+        self.files.borrow_mut().insert(path.to_path_buf(), contents);
+        Ok(())
+    }
+
+    fn exists(&self, path: &Path) -> bool {
+        self.files.borrow().contains_key(path)
+    }
+}
+
+#[test]
+fn loaders_in_memory_fs() {
+    let vfs = MemoryFs::default();
+    let path = Path::new("/memory/Config.toml");
+
+    let err = crate::load_from_path_in::<_, _, Config, _, _>(&vfs, path, |s| toml::from_str(s))
+        .expect_err("Loading a missing file should fail");
+    assert!(matches!(err, ConfigurationError::NotFound { paths } if paths == [path]));
+
+    let config =
+        crate::load_or_default_in(&vfs, path, |s| toml::from_str(s), || Config { range: 3 })
+            .expect("Could not load default config");
+    assert_eq!(config, Config { range: 3 });
+    assert!(!crate::ConfigFs::exists(&vfs, path));
+
+    let config = crate::load_or_write_default_in(
+        &vfs,
+        path,
+        |s| toml::from_str(s),
+        to_toml,
+        || Config { range: 5 },
+    )
+    .expect("Could not write default config");
+    assert_eq!(config, Config { range: 5 });
+    assert_eq!(vfs.files.borrow()[path], "range = 5\n");
+
+    vfs.files
+        .borrow_mut()
+        .insert(path.to_path_buf(), "range = 8".to_string());
+
+    let config: Config =
+        crate::load_from_path_in(&vfs, path, |s| toml::from_str(s)).expect("Could not load config");
+    assert_eq!(config, Config { range: 8 });
+
+    let config = crate::load_or_write_default_in(
+        &vfs,
+        path,
+        |s| toml::from_str(s),
+        to_toml,
+        || Config { range: 5 },
+    )
+    .expect("Could not load config");
+    assert_eq!(config, Config { range: 8 });
+}
+
+#[test]
+fn reload_if_changed_ignores_equal_values() {
+    #[derive(Deserialize, PartialEq, Debug)]
+    struct Pair {
+        a: usize,
+        b: usize,
+    }
+
+    let path = test_dir("reload_if_changed_ignores_equal_values").join("Config.toml");
+    fs::write(&path, "a = 1\nb = 2\n").expect("Could not write config");
+
+    let previous: Pair =
+        crate::load_from_path(&path, |s| toml::from_str(s)).expect("Could not load config");
+
+    fs::write(&path, "b = 2\na = 1\n").expect("Could not write config");
+    let reloaded = crate::reload_if_changed(&path, |s| toml::from_str(s), &previous)
+        .expect("Could not reload config");
+    assert_eq!(reloaded, None);
+
+    fs::write(&path, "b = 3\na = 1\n").expect("Could not write config");
+    let reloaded = crate::reload_if_changed(&path, |s| toml::from_str(s), &previous)
+        .expect("Could not reload config");
+    assert_eq!(reloaded, Some(Pair { a: 1, b: 3 }));
+}
+
+#[test]
+fn config_loader_empty_file_is_error_by_default() {
+    let dir = test_dir("config_loader_empty_file_is_error_by_default");
+
+    for (name, content) in [("Empty.toml", ""), ("Blank.toml", " \n\t\n")] {
+        let path = dir.join(name);
+        fs::write(&path, content).expect("Could not write config");
+
+        let err = ConfigLoader::new(|s| toml::from_str(s))
+            .path(&path)
+            .or_default(|| Config { range: 3 })
+            .load()
+            .expect_err("An empty file should fail to deserialize");
+        assert!(matches!(err, ConfigurationError::Deserialize(_)));
+    }
+}
+
+#[test]
+fn config_loader_empty_as_missing() {
+    let dir = test_dir("config_loader_empty_as_missing");
+
+    for (name, content) in [("Empty.toml", ""), ("Blank.toml", " \n\t\n")] {
+        let path = dir.join(name);
+        fs::write(&path, content).expect("Could not write config");
+
+        let config = ConfigLoader::new(|s| toml::from_str(s))
+            .path(&path)
+            .or_default(|| Config { range: 3 })
+            .empty_as_missing(true)
+            .load()
+            .expect("Could not load default config");
+        assert_eq!(config, Config { range: 3 });
+        assert_eq!(fs::read_to_string(&path).unwrap(), content);
+
+        let config = ConfigLoader::new(|s| toml::from_str(s))
+            .path(&path)
+            .or_default(|| Config { range: 4 })
+            .write_if_missing(to_toml)
+            .empty_as_missing(true)
+            .load()
+            .expect("Could not write default config");
+        assert_eq!(config, Config { range: 4 });
+        assert_eq!(fs::read_to_string(&path).unwrap(), "range = 4\n");
+    }
+}
+
+#[test]
+fn load_profile_selects_section() {
+    use std::collections::HashMap;
+
+    let path = test_dir("load_profile_selects_section").join("Config.toml");
+    fs::write(&path, "[dev]\nrange = 1\n\n[prod]\nrange = 2\n").expect("Could not write config");
+
+    let select = |s: &str, profile: &str| {
+        let mut profiles: HashMap<String, Config> = toml::from_str(s).map_err(|e| e.to_string())?;
+        profiles
+            .remove(profile)
+            .ok_or_else(|| format!("Missing profile {profile}"))
+    };
+
+    let config = crate::load_profile(&path, "prod", select).expect("Could not load config");
+    assert_eq!(config, Config { range: 2 });
+
+    let err =
+        crate::load_profile(&path, "test", select).expect_err("A missing profile should fail");
+    assert!(matches!(err, ConfigurationError::Deserialize(ref e) if e == "Missing profile test"));
+}
+
+#[test]
+fn load_or_partial_fills_missing_fields() {
+    #[derive(PartialEq, Debug)]
+    struct Full {
+        a: usize,
+        b: String,
+        c: bool,
+    }
+
+    #[derive(Deserialize)]
+    struct Partial {
+        a: Option<usize>,
+        b: Option<String>,
+        c: Option<bool>,
+    }
+
+    let path = test_dir("load_or_partial_fills_missing_fields").join("Config.toml");
+    let load = || {
+        crate::load_or_partial(
+            &path,
+            |s| toml::from_str::<Partial>(s),
+            || Full {
+                a: 1,
+                b: "default".to_string(),
+                c: true,
+            },
+            |default, partial| Full {
+                a: partial.a.unwrap_or(default.a),
+                b: partial.b.unwrap_or(default.b),
+                c: partial.c.unwrap_or(default.c),
+            },
+        )
+    };
+
+    let config = load().expect("Could not load default config");
+    assert_eq!(config.b, "default");
+
+    fs::write(&path, "b = 'file'").expect("Could not write config");
+    let config = load().expect("Could not load config");
+    assert_eq!(
+        config,
+        Full {
+            a: 1,
+            b: "file".to_string(),
+            c: true,
+        }
+    );
+}
+
+#[test]
+fn load_or_try_default_propagates_default_error() {
+    let path = test_dir("load_or_try_default_propagates_default_error").join("Config.toml");
+    let deserializer = |s: &str| toml::from_str(s).map_err(|e| e.to_string());
+
+    let err = crate::load_or_try_default(&path, deserializer, || {
+        Err::<Config, _>("No default".to_string())
+    })
+    .expect_err("A failing default should fail");
+    assert!(matches!(err, ConfigurationError::Default(ref e) if e == "No default"));
+    assert_eq!(
+        err.to_string(),
+        "Could not compute the default configuration: No default"
+    );
+    assert_eq!(io::Error::from(err).kind(), io::ErrorKind::Other);
+
+    let config = crate::load_or_try_default(&path, deserializer, || Ok(Config { range: 2 }))
+        .expect("Could not load default config");
+    assert_eq!(config, Config { range: 2 });
+
+    fs::write(&path, "range = 3").expect("Could not write config");
+    let config = crate::load_or_try_default(&path, deserializer, || {
+        Err::<Config, _>("No default".to_string())
+    })
+    .expect("Could not load config");
+    assert_eq!(config, Config { range: 3 });
+}
+
+#[test]
+fn load_or_write_default_durable() {
+    let dir = test_dir("load_or_write_default_durable");
+    let path = dir.join("nested").join("Config.toml");
+
+    let config = crate::load_or_write_default_durable(
+        &path,
+        |s| toml::from_str(s),
+        to_toml,
+        || Config { range: 6 },
+    )
+    .expect("Could not write default config");
+    assert_eq!(config, Config { range: 6 });
+    assert_eq!(fs::read_to_string(&path).unwrap(), "range = 6\n");
+    assert_eq!(fs::read_dir(path.parent().unwrap()).unwrap().count(), 1);
+
+    let config = crate::load_or_write_default_durable(
+        &path,
+        |s| toml::from_str(s),
+        to_toml,
+        Config::default,
+    )
+    .expect("Could not load config");
+    assert_eq!(config, Config { range: 6 });
+}
+
+/// A [crate::ConfigFs] delegating to [crate::StdFs], recording the paths it is asked to sync.
+#[derive(Default)]
+struct SyncRecordingFs {
+    synced: std::cell::RefCell<Vec<PathBuf>>,
+}
+
+impl crate::ConfigFs for SyncRecordingFs {
+    fn read(&self, path: &Path) -> io::Result<String> {
+        crate::StdFs.read(path)
+    }
+
+    fn write(&self, path: &Path, contents: &[u8]) -> io::Result<()> {
+        crate::StdFs.write(path, contents)
+    }
+
+    fn exists(&self, path: &Path) -> bool {
+        crate::StdFs.exists(path)
+    }
+
+    fn sync(&self, path: &Path) -> io::Result<()> {
+        self.synced.borrow_mut().push(path.to_path_buf());
+        crate::StdFs.sync(path)
+    }
+}
+
+#[test]
+fn load_or_write_default_durable_in_syncs_file_and_parent() {
+    use crate::ConfigFs;
+
+    let dir = test_dir("load_or_write_default_durable_in_syncs_file_and_parent");
+    let path = dir.join("nested").join("Config.toml");
+    let vfs = SyncRecordingFs::default();
+
+    let config = crate::load_or_write_default_durable_in(
+        &vfs,
+        &path,
+        |s| toml::from_str(s),
+        to_toml,
+        || Config { range: 6 },
+    )
+    .expect("Could not write default config");
+    assert_eq!(config, Config { range: 6 });
+    assert_eq!(*vfs.synced.borrow(), [path.clone(), dir.join("nested")]);
+
+    vfs.synced.borrow_mut().clear();
+    let config = crate::load_or_write_default_durable_in(
+        &vfs,
+        &path,
+        |s| toml::from_str(s),
+        to_toml,
+        Config::default,
+    )
+    .expect("Could not load config");
+    assert_eq!(config, Config { range: 6 });
+    assert!(vfs.synced.borrow().is_empty());
+
+    let memory = MemoryFs::default();
+    let config = crate::load_or_write_default_durable_in(
+        &memory,
+        Path::new("/memory/Config.toml"),
+        |s| toml::from_str(s),
+        to_toml,
+        || Config { range: 6 },
+    )
+    .expect("Could not write default config");
+    assert_eq!(config, Config { range: 6 });
+    assert!(memory.exists(Path::new("/memory/Config.toml")));
+}
+
+#[cfg(feature = "log")]
+#[test]
+fn load_or_write_default_logs_writing_default() {
+    use std::sync::Mutex;
+
+    /// A logger which captures every message, as tests are run in parallel.
+    struct CaptureLogger(Mutex<Vec<String>>);
+
+    impl log::Log for CaptureLogger {
+        fn enabled(&self, _: &log::Metadata) -> bool {
+            true
+        }
+
+        fn log(&self, record: &log::Record) {
+            self.0.lock().unwrap().push(record.args().to_string());
+        }
+
+        fn flush(&self) {}
+    }
+
+    static LOGGER: CaptureLogger = CaptureLogger(Mutex::new(Vec::new()));
+    let _ = log::set_logger(&LOGGER);
+    log::set_max_level(log::LevelFilter::Trace);
+
+    let path = test_dir("load_or_write_default_logs_writing_default").join("Config.toml");
+    crate::load_or_write_default(&path, |s| toml::from_str(s), to_toml, Config::default)
+        .expect("Could not write default config");
+
+    let expected = format!(
+        "Configuration file '{}' does not exist, writing default",
+        path.display()
+    );
+    assert!(LOGGER.0.lock().unwrap().contains(&expected));
+}
+
+#[test]
+fn write_default_replaces_existing_file() {
+    let path = test_dir("write_default_replaces_existing_file").join("Config.toml");
+    fs::write(&path, "# Custom\nrange = 9\n").expect("Could not write config");
+
+    let config = crate::write_default(&path, to_toml, || Config { range: 2 })
+        .expect("Could not write default config");
+    assert_eq!(config, Config { range: 2 });
+    assert_eq!(fs::read_to_string(&path).unwrap(), "range = 2\n");
+}
+
+#[cfg(feature = "toml-edit")]
+#[test]
+fn edit_toml_preserves_comments() {
+    let path = test_dir("edit_toml_preserves_comments").join("Config.toml");
+    fs::write(
+        &path,
+        "# The range of the thing\nrange = 1 # inline\n\n# Other\nname = 'a'\n",
+    )
+    .expect("Could not write config");
+
+    let previous = crate::edit_toml(&path, |doc| {
+        let previous = doc["range"].as_integer();
+        doc["range"] = toml_edit::value(5);
+        previous
+    })
+    .expect("Could not edit config");
+    assert_eq!(previous, Some(1));
+
+    let content = fs::read_to_string(&path).unwrap();
+    assert!(content.starts_with("# The range of the thing\nrange = 5"));
+    assert!(content.contains("\n# Other\nname = 'a'\n"));
+
+    fs::write(&path, "range = ").expect("Could not write config");
+    let err = crate::edit_toml(&path, |_| ()).expect_err("Invalid content should fail");
+    assert!(matches!(err, ConfigurationError::Deserialize(_)));
+    assert_eq!(fs::read_to_string(&path).unwrap(), "range = ");
+}
+
+#[test]
+fn load_with_raw_returns_content() {
+    let path = test_dir("load_with_raw_returns_content").join("Config.toml");
+    let content = "# Comment\nrange   =   4\n";
+    fs::write(&path, content).expect("Could not write config");
+
+    let (config, raw): (Config, _) =
+        crate::load_with_raw(&path, |s| toml::from_str(s)).expect("Could not load config");
+    assert_eq!(config, Config { range: 4 });
+    assert_eq!(raw, content);
+}
+
+#[cfg(unix)]
+#[test]
+fn symlink_policies() {
+    use std::os::unix::fs::symlink;
+
+    use crate::LoadOptions;
+
+    let dir = test_dir("symlink_policies");
+    let target = dir.join("Target.toml");
+    let link = dir.join("Config.toml");
+    symlink("Target.toml", &link).expect("Could not create symlink");
+
+    let follow = LoadOptions::default();
+
+    let config = crate::load_or_write_default_with_options(
+        &link,
+        |s| toml::from_str(s),
+        to_toml,
+        || Config { range: 2 },
+        &follow,
+    )
+    .expect("Could not write default config");
+    assert_eq!(config, Config { range: 2 });
+    assert_eq!(fs::read_to_string(&target).unwrap(), "range = 2\n");
+
+    crate::save_to_path_with_options(&link, &Config { range: 3 }, to_toml, &follow)
+        .expect("Could not save config");
+    assert!(fs::symlink_metadata(&link)
+        .unwrap()
+        .file_type()
+        .is_symlink());
+    assert_eq!(fs::read_to_string(&target).unwrap(), "range = 3\n");
+
+    let canonicalize = LoadOptions {
+        canonicalize: true,
+        ..LoadOptions::default()
+    };
+
+    crate::save_to_path_with_options(&link, &Config { range: 4 }, to_toml, &canonicalize)
+        .expect("Could not save config");
+    assert!(fs::symlink_metadata(&link)
+        .unwrap()
+        .file_type()
+        .is_symlink());
+    assert_eq!(fs::read_to_string(&target).unwrap(), "range = 4\n");
+
+    let refuse = LoadOptions {
+        follow_symlinks: false,
+        ..LoadOptions::default()
+    };
+
+    let err = crate::load_from_path_with_options::<_, Config, _, _>(
+        &link,
+        |s| toml::from_str(s),
+        &refuse,
+    )
+    .expect_err("Loading a symlink should be refused");
+    assert!(
+        matches!(err, ConfigurationError::Io { ref source, .. } if source.kind() == io::ErrorKind::InvalidInput)
+    );
+
+    let config: Config =
+        crate::load_from_path_with_options(&target, |s| toml::from_str(s), &refuse)
+            .expect("Could not load config");
+    assert_eq!(config, Config { range: 4 });
+}
+
+#[cfg(all(unix, feature = "no-follow"))]
+#[test]
+fn load_from_path_no_follow_refuses_symlinks() {
+    let dir = test_dir("load_from_path_no_follow_refuses_symlinks");
+    let target = dir.join("Target.toml");
+    let link = dir.join("Config.toml");
+    fs::write(&target, "range = 5").expect("Could not write config");
+    std::os::unix::fs::symlink(&target, &link).expect("Could not create symlink");
+
+    let err = crate::load_from_path_no_follow::<_, Config, _, _>(&link, |s| toml::from_str(s))
+        .expect_err("Loading a symlink should fail");
+    assert!(matches!(err, ConfigurationError::Io { ref path, .. } if *path == Some(link.clone())));
+
+    let config: Config = crate::load_from_path_no_follow(&target, |s| toml::from_str(s))
+        .expect("Could not load config");
+    assert_eq!(config, Config { range: 5 });
+}
+
+#[cfg(feature = "json")]
+#[test]
+fn boxed_loaders_share_a_type() {
+    type Loader = Box<dyn Fn() -> crate::Result<Config, crate::BoxError>>;
+
+    let dir = test_dir("boxed_loaders_share_a_type");
+    let toml_path = dir.join("Config.toml");
+    let json_path = dir.join("Config.json");
+    fs::write(&toml_path, "range = 1").expect("Could not write config");
+    fs::write(&json_path, "{ \"range\": ").expect("Could not write config");
+
+    let loaders: Vec<Loader> = vec![
+        Box::new(move || crate::load_boxed(&toml_path, |s| toml::from_str(s))),
+        Box::new(move || crate::load_boxed(&json_path, |s| serde_json::from_str(s))),
+    ];
+
+    let config = loaders[0]().expect("Could not load config");
+    assert_eq!(config, Config { range: 1 });
+
+    let err = loaders[1]().expect_err("Invalid content should fail");
+    let ConfigurationError::Deserialize(err) = err else {
+        panic!("Expected a deserializer error, got {err:?}");
+    };
+    assert!(err.downcast_ref::<serde_json::Error>().is_some());
+}
+
+#[test]
+fn is_first_run_until_default_written() {
+    let path = test_dir("is_first_run_until_default_written").join("Config.toml");
+    assert!(crate::is_first_run(&path));
+
+    let (_, meta) = crate::load_or_write_default_with_meta(
+        &path,
+        |s| toml::from_str(s),
+        to_toml,
+        || Config { range: 1 },
+    )
+    .expect("Could not write default config");
+    assert!(meta.is_first_run());
+    assert!(!crate::is_first_run(&path));
+
+    let (_, meta) = crate::load_or_write_default_with_meta(
+        &path,
+        |s| toml::from_str(s),
+        to_toml,
+        Config::default,
+    )
+    .expect("Could not load config");
+    assert!(!meta.is_first_run());
+}
+
+#[test]
+fn load_or_embedded_parses_embedded_content() {
+    const EMBEDDED: &str = "# Built-in defaults\nrange = 7\n";
+
+    let path = test_dir("load_or_embedded_parses_embedded_content").join("Config.toml");
+
+    let config: Config = crate::load_or_embedded(&path, |s| toml::from_str(s), EMBEDDED)
+        .expect("Could not load embedded config");
+    assert_eq!(config, Config { range: 7 });
+    assert!(!path.exists());
+
+    let err = crate::load_or_embedded::<_, Config, _, _>(&path, |s| toml::from_str(s), "range =")
+        .expect_err("Invalid embedded content should fail");
+    assert!(matches!(err, ConfigurationError::Deserialize(_)));
+
+    fs::write(&path, "range = 1").expect("Could not write config");
+    let config: Config = crate::load_or_embedded(&path, |s| toml::from_str(s), EMBEDDED)
+        .expect("Could not load config");
+    assert_eq!(config, Config { range: 1 });
+}
+
+#[test]
+fn shared_config_reload_is_seen_by_readers() {
+    use std::sync::mpsc;
+    use std::thread;
+
+    let path = test_dir("shared_config_reload_is_seen_by_readers").join("Config.toml");
+    fs::write(&path, "range = 1").expect("Could not write config");
+
+    let config = crate::SharedConfig::load(&path, |s| toml::from_str::<Config>(s))
+        .expect("Could not load config");
+
+    let (reloaded, wait_reload) = mpsc::channel();
+    let reader = config.clone();
+    let handle = thread::spawn(move || {
+        let before = reader.read().range;
+        wait_reload.recv().unwrap();
+        let after = reader.read().range;
+        (before, after)
+    });
+
+    fs::write(&path, "range = 2").expect("Could not write config");
+    config.reload().expect("Could not reload config");
+    reloaded.send(()).unwrap();
+
+    let (before, after) = handle.join().unwrap();
+    assert!(before == 1 || before == 2);
+    assert_eq!(after, 2);
+
+    fs::write(&path, "range = ").expect("Could not write config");
+    assert!(config.reload().is_err());
+    assert_eq!(config.read().range, 2);
+}
+
+#[test]
+fn check_path_reports_validity() {
+    let dir = test_dir("check_path_reports_validity");
+    let valid = dir.join("Valid.toml");
+    let invalid = dir.join("Invalid.toml");
+    fs::write(&valid, "range = 1").expect("Could not write config");
+    fs::write(&invalid, "range = 'one'").expect("Could not write config");
+
+    crate::check_path(&valid, |s| toml::from_str::<Config>(s)).expect("The config should be valid");
+
+    let err = crate::check_path(&invalid, |s| toml::from_str::<Config>(s))
+        .expect_err("The config should be invalid");
+    assert!(matches!(err, ConfigurationError::Deserialize(_)));
+}
+
+#[test]
+fn load_from_path_bounded_rejects_large_files() {
+    let path = test_dir("load_from_path_bounded_rejects_large_files").join("Config.toml");
+    fs::write(&path, "range = 12").expect("Could not write config");
+
+    let config: Config = crate::load_from_path_bounded(&path, |s| toml::from_str(s), 10)
+        .expect("Could not load config");
+    assert_eq!(config, Config { range: 12 });
+
+    let err = crate::load_from_path_bounded::<_, Config, _, _>(&path, |s| toml::from_str(s), 9)
+        .expect_err("A file over the limit should fail");
+    assert!(matches!(
+        err,
+        ConfigurationError::TooLarge { ref path, size: 10, limit: 9 } if path.ends_with("Config.toml")
+    ));
+}
+
+#[test]
+fn load_from_path_progress_reports_bytes_read() {
+    let path = test_dir("load_from_path_progress_reports_bytes_read").join("Config.toml");
+    let content = format!("range = 3\n{}", "# padding\n".repeat(2048));
+    fs::write(&path, &content).expect("Could not write config");
+
+    let mut calls = Vec::new();
+    let config: Config = crate::load_from_path_progress(
+        &path,
+        |s| toml::from_str(s),
+        |read, total| calls.push((read, total)),
+    )
+    .expect("Could not load config");
+    assert_eq!(config, Config { range: 3 });
+
+    let size = content.len() as u64;
+    assert!(!calls.is_empty());
+    assert!(calls.windows(2).all(|w| w[0].0 < w[1].0));
+    assert!(calls.iter().all(|&(_, total)| total == size));
+    assert_eq!(calls.last(), Some(&(size, size)));
+}
+
+#[test]
+fn config_loader_max_size() {
+    let path = test_dir("config_loader_max_size").join("Config.toml");
+    fs::write(&path, format!("range = 1\n{}", "#".repeat(64))).expect("Could not write config");
+
+    let err = ConfigLoader::new(|s| toml::from_str(s))
+        .path(&path)
+        .or_default(Config::default)
+        .max_size(16)
+        .load()
+        .expect_err("A file over the limit should fail");
+    assert!(matches!(
+        err,
+        ConfigurationError::TooLarge {
+            size: 74,
+            limit: 16,
+            ..
+        }
+    ));
+
+    let config: Config = ConfigLoader::new(|s| toml::from_str(s))
+        .path(&path)
+        .max_size(1024)
+        .load()
+        .expect("Could not load config");
+    assert_eq!(config, Config { range: 1 });
+}
+
+#[cfg(feature = "json")]
+#[test]
+fn json_pretty_and_compact() {
+    let dir = test_dir("json_pretty_and_compact");
+    let pretty = dir.join("Pretty.json");
+    let compact = dir.join("Compact.json");
+
+    let _: Config =
+        crate::load_or_write_default_json(&pretty, true).expect("Could not write config");
+    let _: Config =
+        crate::load_or_write_default_json(&compact, false).expect("Could not write config");
+    assert_eq!(fs::read_to_string(&pretty).unwrap(), "{\n  \"range\": 0\n}");
+    assert_eq!(fs::read_to_string(&compact).unwrap(), "{\"range\":0}");
+
+    crate::save_json(&pretty, &Config { range: 2 }, true).expect("Could not save config");
+    crate::save_json(&compact, &Config { range: 2 }, false).expect("Could not save config");
+    assert!(fs::read_to_string(&pretty).unwrap().contains('\n'));
+    assert!(!fs::read_to_string(&compact).unwrap().contains('\n'));
+}
+
+#[cfg(feature = "dirs")]
+#[test]
+fn expand_tilde() {
+    let home = directories::BaseDirs::new()
+        .expect("Could not find a home directory")
+        .home_dir()
+        .to_path_buf();
+
+    assert_eq!(crate::expand_tilde("~/foo"), home.join("foo"));
+    assert_eq!(crate::expand_tilde("~"), home);
+    assert_eq!(crate::expand_tilde("foo/~/bar"), Path::new("foo/~/bar"));
+    assert_eq!(crate::expand_tilde("~foo/bar"), Path::new("~foo/bar"));
+
+    let options = crate::LoadOptions {
+        expand_tilde: true,
+        ..crate::LoadOptions::default()
+    };
+    let name = format!("graze-{}-missing.toml", std::process::id());
+
+    let err = crate::load_from_path_with_options::<_, Config, _, _>(
+        format!("~/{name}"),
+        |s| toml::from_str(s),
+        &options,
+    )
+    .expect_err("Loading a missing file should fail");
+    assert!(matches!(err, ConfigurationError::NotFound { paths } if paths == [home.join(name)]));
+}
+
+#[test]
+fn load_or_partial_with_report_names_file_fields() {
+    #[derive(Default)]
+    struct Full {
+        a: usize,
+        b: usize,
+        c: usize,
+    }
+
+    #[derive(Deserialize)]
+    struct Partial {
+        a: Option<usize>,
+        b: Option<usize>,
+        c: Option<usize>,
+    }
+
+    let path = test_dir("load_or_partial_with_report_names_file_fields").join("Config.toml");
+    fs::write(&path, "b = 2").expect("Could not write config");
+
+    let (config, report) = crate::load_or_partial_with_report(
+        &path,
+        |s| toml::from_str::<Partial>(s),
+        Full::default,
+        |mut config, partial, report| {
+            for (name, field, value) in [
+                ("a", &mut config.a, partial.a),
+                ("b", &mut config.b, partial.b),
+                ("c", &mut config.c, partial.c),
+            ] {
+                if let Some(value) = value {
+                    *field = value;
+                    report.record(name);
+                }
+            }
+            config
+        },
+    )
+    .expect("Could not load config");
+
+    assert_eq!((config.a, config.b, config.c), (0, 2, 0));
+    assert_eq!(report.from_file(), ["b"]);
+    assert!(report.is_from_file("b"));
+    assert!(!report.is_from_file("a"));
+}
+
+#[test]
+fn load_directory_is_is_directory_error() {
+    let path = test_dir("load_directory_is_is_directory_error");
+
+    let err = crate::load_from_path::<_, Config, _, _>(&path, |s| toml::from_str(s))
+        .expect_err("Loading a directory should fail");
+    assert!(matches!(err, ConfigurationError::IsDirectory { path: ref p } if *p == path));
+    assert!(err.to_string().contains("is a directory"));
+
+    let err = crate::load_from_path_bounded::<_, Config, _, _>(&path, |s| toml::from_str(s), 1024)
+        .expect_err("Loading a directory should fail");
+    assert!(matches!(err, ConfigurationError::IsDirectory { .. }));
+}
+
+#[cfg(feature = "flat")]
+#[test]
+fn parse_flat_key_values() {
+    let config = crate::parse_flat("message = Hello, world!\nquoted = \" padded \"\nempty =\n")
+        .expect("Could not parse config");
+
+    assert_eq!(config.len(), 3);
+    assert_eq!(config["message"], "Hello, world!");
+    assert_eq!(config["quoted"], " padded ");
+    assert_eq!(config["empty"], "");
+}
+
+#[cfg(feature = "flat")]
+#[test]
+fn parse_flat_skips_comments_and_blank_lines() {
+    let config = crate::parse_flat("# A comment\n\n   \n  # Indented\nurl = http://a/#b\n")
+        .expect("Could not parse config");
+
+    assert_eq!(config.len(), 1);
+    assert_eq!(config["url"], "http://a/#b");
+}
+
+#[cfg(feature = "flat")]
+#[test]
+fn parse_flat_reports_line() {
+    assert_eq!(
+        crate::parse_flat("a = 1\n\nmissing\n"),
+        Err(crate::ParseError::MissingSeparator { line: 3 })
+    );
+    assert_eq!(
+        crate::parse_flat(" = 1"),
+        Err(crate::ParseError::EmptyKey { line: 1 })
+    );
+}
+
+#[cfg(feature = "flat")]
+#[test]
+fn load_flat() {
+    let path = test_dir("load_flat").join("Config.env");
+    fs::write(&path, "message = first\nmessage = second\n").expect("Could not write config");
+
+    let config = crate::load_flat(&path).expect("Could not load config");
+    assert_eq!(config["message"], "second");
+
+    let err = crate::load_flat(path.with_extension("missing")).expect_err("Should be missing");
+    assert!(matches!(err, ConfigurationError::NotFound { .. }));
+}
+
+#[cfg(feature = "flat")]
+#[test]
+fn layer_env_file_overrides_fields() {
+    #[derive(Debug, PartialEq)]
+    struct Settings {
+        host: String,
+        token: String,
+        port: String,
+    }
+
+    let path = test_dir("layer_env_file_overrides_fields").join(".env");
+    fs::write(
+        &path,
+        "# Local overrides\nexport HOST=example.com # local\nTOKEN = \"s3cr#t\" # quoted\nPORT=#9090\nUNUSED=1\n",
+    )
+    .expect("Could not write env file");
+
+    let mut settings = Settings {
+        host: "localhost".to_string(),
+        token: String::new(),
+        port: "8080".to_string(),
+    };
+    crate::layer_env_file(&mut settings, &path, |settings, key, value| match key {
+        "HOST" => settings.host = value.to_string(),
+        "TOKEN" => settings.token = value.to_string(),
+        "PORT" => settings.port = value.to_string(),
+        _ => {}
+    })
+    .expect("Could not layer env file");
+
+    assert_eq!(
+        settings,
+        Settings {
+            host: "example.com".to_string(),
+            token: "s3cr#t".to_string(),
+            port: String::new(),
+        }
+    );
+}
+
+#[cfg(feature = "flat")]
+#[test]
+fn layer_env_file_keeps_hash_inside_unquoted_value() {
+    let path = test_dir("layer_env_file_keeps_hash_inside_unquoted_value").join(".env");
+    fs::write(&path, "export  URL=http://a/#b\nNAME='x # y' trailing\n")
+        .expect("Could not write env file");
+
+    let mut pairs = Vec::new();
+    crate::layer_env_file(&mut pairs, &path, |pairs, key, value| {
+        pairs.push((key.to_string(), value.to_string()))
+    })
+    .expect("Could not layer env file");
+
+    assert_eq!(
+        pairs,
+        [
+            ("URL".to_string(), "http://a/#b".to_string()),
+            ("NAME".to_string(), "x # y".to_string()),
+        ]
+    );
+}
+
+#[test]
+fn load_into_env_respects_overwrite() {
+    use std::collections::HashMap;
+
+    let path = test_dir("load_into_env_respects_overwrite").join("Env.toml");
+    fs::write(
+        &path,
+        "GRAZE_TEST_INTO_ENV_NEW = \"new\"\nGRAZE_TEST_INTO_ENV_SET = \"loaded\"\n",
+    )
+    .expect("Could not write config");
+    let load = |overwrite| {
+        crate::load_into_env(
+            &path,
+            |s| toml::from_str::<HashMap<String, String>>(s),
+            overwrite,
+        )
+        .expect("Could not load environment")
+    };
+
+    std::env::set_var("GRAZE_TEST_INTO_ENV_SET", "existing");
+    load(false);
+    assert_eq!(
+        std::env::var("GRAZE_TEST_INTO_ENV_NEW").as_deref(),
+        Ok("new")
+    );
+    assert_eq!(
+        std::env::var("GRAZE_TEST_INTO_ENV_SET").as_deref(),
+        Ok("existing")
+    );
+
+    load(true);
+    assert_eq!(
+        std::env::var("GRAZE_TEST_INTO_ENV_SET").as_deref(),
+        Ok("loaded")
+    );
+}
+
+#[test]
+fn load_into_env_rejects_invalid_names() {
+    use std::collections::HashMap;
+
+    let path = test_dir("load_into_env_rejects_invalid_names").join("Env.toml");
+    fs::write(
+        &path,
+        "GRAZE_TEST_INTO_ENV_VALID = \"valid\"\n\"A=B\" = \"invalid\"\n",
+    )
+    .expect("Could not write config");
+
+    let err = crate::load_into_env(
+        &path,
+        |s| toml::from_str::<HashMap<String, String>>(s),
+        true,
+    )
+    .expect_err("An invalid name should fail");
+    assert!(
+        matches!(err, ConfigurationError::Io { ref source, .. } if source.kind() == io::ErrorKind::InvalidInput)
+    );
+    assert!(std::env::var_os("GRAZE_TEST_INTO_ENV_VALID").is_none());
+    assert!(std::env::var_os("A").is_none());
+}
+
+#[cfg(feature = "schema")]
+#[test]
+fn write_schema_lists_fields() {
+    #[derive(schemars::JsonSchema)]
+    #[allow(dead_code)]
+    struct Schema {
+        message: String,
+        range: usize,
+    }
+
+    let path = test_dir("write_schema_lists_fields").join("Config.schema.json");
+    crate::write_schema::<Schema, _>(&path).expect("Could not write schema");
+
+    let content = fs::read_to_string(&path).expect("Could not read schema");
+    let schema: serde_json::Value = serde_json::from_str(&content).expect("Invalid schema JSON");
+    let properties = schema["properties"]
+        .as_object()
+        .expect("Schema has no properties");
+    assert!(properties.contains_key("message"));
+    assert!(properties.contains_key("range"));
+}
+
+#[cfg(all(unix, feature = "signal"))]
+#[test]
+fn reload_on_sighup() {
+    let path = test_dir("reload_on_sighup").join("Config.toml");
+    fs::write(&path, "range = 1").expect("Could not write config");
+
+    let config = crate::SharedConfig::load(&path, |s| toml::from_str::<Config>(s))
+        .expect("Could not load config");
+    let _reloader = crate::reload_on_sighup(config.clone()).expect("Could not install handler");
+
+    fs::write(&path, "range = 2").expect("Could not write config");
+    signal_hook::low_level::raise(signal_hook::consts::SIGHUP).expect("Could not raise SIGHUP");
+
+    for _ in 0..100 {
+        if config.read().range == 2 {
+            return;
+        }
+        std::thread::sleep(Duration::from_millis(10));
+    }
+    panic!("The configuration was not reloaded");
+}
+
+#[cfg(feature = "encoding")]
+#[test]
+fn load_from_path_encoded_windows_1252() {
+    use std::collections::HashMap;
+
+    let path = test_dir("load_from_path_encoded_windows_1252").join("Config.toml");
+    fs::write(&path, b"message = \"caf\xe9\"\n").expect("Could not write config");
+
+    let config: HashMap<String, String> =
+        crate::load_from_path_encoded(&path, encoding_rs::WINDOWS_1252, |s| toml::from_str(s))
+            .expect("Could not load config");
+    assert_eq!(config["message"], "caf\u{e9}");
+
+    let err =
+        crate::load_from_path::<_, HashMap<String, String>, _, _>(&path, |s| toml::from_str(s))
+            .expect_err("Windows-1252 content is not valid UTF-8");
+    assert!(matches!(err, ConfigurationError::Io { .. }));
+}
+
+#[cfg(feature = "http")]
+#[test]
+fn load_from_url() {
+    let body = "range = 4\n";
+    let (url, server) = serve_http(vec![
+        format!(
+            "HTTP/1.1 200 OK\r\nContent-Length: {}\r\nConnection: close\r\n\r\n{body}",
+            body.len()
+        ),
+        "HTTP/1.1 404 Not Found\r\nContent-Length: 0\r\nConnection: close\r\n\r\n".to_string(),
+    ]);
+
+    let config: Config =
+        crate::load_from_url(&url, |s| toml::from_str(s)).expect("Could not load config");
+    assert_eq!(config, Config { range: 4 });
+
+    let err = crate::load_from_url::<Config, _, _>(&url, |s| toml::from_str(s))
+        .expect_err("A 404 response should fail");
+    assert!(matches!(
+        err,
+        ConfigurationError::HttpStatus { status: 404, .. }
+    ));
+
+    let requests = server.join().expect("The test server panicked");
+    assert!(requests[0].starts_with("GET /Config.toml HTTP/1.1"));
+}
+
+#[cfg(feature = "http")]
+#[test]
+fn remote_config_keeps_value_when_not_modified() {
+    let body = "range = 4\n";
+    let (url, server) = serve_http(vec![
+        format!(
+            "HTTP/1.1 200 OK\r\nETag: \"v1\"\r\nContent-Length: {}\r\nConnection: close\r\n\r\n{body}",
+            body.len()
+        ),
+        "HTTP/1.1 304 Not Modified\r\nETag: \"v1\"\r\nConnection: close\r\n\r\n".to_string(),
+    ]);
+
+    let mut remote = crate::RemoteConfig::load(&url, |s| toml::from_str::<Config>(s))
+        .expect("Could not load config");
+    assert_eq!(remote.etag(), Some("\"v1\""));
+
+    let changed = remote.refresh().expect("Could not refresh config");
+    assert!(!changed);
+    assert_eq!(*remote.get(), Config { range: 4 });
+
+    let requests = server.join().expect("The test server panicked");
+    assert!(!requests[0].to_lowercase().contains("if-none-match"));
+    assert!(requests[1].to_lowercase().contains("if-none-match: \"v1\""));
 }
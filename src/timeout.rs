@@ -0,0 +1,99 @@
+//! Bounding the time spent reading configurations from slow sources.
+
+use std::io::Read;
+use std::path::Path;
+use std::sync::mpsc;
+use std::time::Duration;
+use std::{fs, io, thread};
+
+use crate::{decode_content, load_from_str, read_error, Result};
+
+/// Load a configuration from the file at the given path, failing if the file cannot be read within
+/// the given timeout.
+///
+/// The file is read on a separate thread, so that a read blocking on an unresponsive filesystem
+/// (e.g. a network mount) cannot block the caller indefinitely. If the read does not complete in
+/// time, [ConfigurationError::Io](crate::ConfigurationError::Io) is returned with
+/// [io::ErrorKind::TimedOut], and the thread is left to finish the read in the background. See
+/// [load_from_path](crate::load_from_path) for details.
+///
+/// # Examples
+///
+/// ```no_run
+/// use std::time::Duration;
+///
+/// use serde::Deserialize;
+///
+/// #[derive(Deserialize)]
+/// struct Config {
+///     message: String
+/// }
+///
+/// let config: Config = graze::load_from_path_timeout(
+///     "/mnt/share/Config.toml",
+///     |c| toml::from_str(c),
+///     Duration::from_secs(5),
+/// )
+/// .expect("Could not load configuration");
+/// ```
+pub fn load_from_path_timeout<P, T, E, D>(
+    path: P,
+    deserializer: D,
+    timeout: Duration,
+) -> Result<T, E>
+where
+    P: AsRef<Path>,
+    D: FnOnce(&str) -> std::result::Result<T, E>,
+{
+    let path = path.as_ref();
+    let owned = path.to_path_buf();
+
+    let content =
+        read_with_timeout(timeout, move || fs::read(owned)).map_err(|err| read_error(path, err))?;
+    let content = decode_content(path, content)?;
+
+    load_from_str(&content, deserializer)
+}
+
+/// Load a configuration from the given reader, failing if the reader cannot be read to the end
+/// within the given timeout.
+///
+/// See [load_from_path_timeout] and [load_from_reader](crate::load_from_reader) for details.
+pub fn load_from_reader_timeout<R, T, E, D>(
+    mut reader: R,
+    deserializer: D,
+    timeout: Duration,
+) -> Result<T, E>
+where
+    R: Read + Send + 'static,
+    D: FnOnce(&str) -> std::result::Result<T, E>,
+{
+    let content = read_with_timeout(timeout, move || {
+        let mut content = String::new();
+        reader.read_to_string(&mut content)?;
+        Ok(content)
+    })?;
+
+    load_from_str(&content, deserializer)
+}
+
+/// Call `read` on a separate thread, returning an [io::ErrorKind::TimedOut] error if it does not
+/// complete within `timeout`.
+fn read_with_timeout<R, C>(timeout: Duration, read: R) -> io::Result<C>
+where
+    R: FnOnce() -> io::Result<C> + Send + 'static,
+    C: Send + 'static,
+{
+    let (sender, receiver) = mpsc::channel();
+
+    thread::spawn(move || {
+        let _ = sender.send(read());
+    });
+
+    receiver.recv_timeout(timeout).unwrap_or_else(|_| {
+        Err(io::Error::new(
+            io::ErrorKind::TimedOut,
+            "The configuration could not be read in time",
+        ))
+    })
+}
@@ -0,0 +1,233 @@
+//! Loading configurations through a filesystem abstraction.
+
+use std::convert::Infallible;
+use std::io;
+use std::path::Path;
+
+use crate::{
+    create_parent_dirs, load_from_str, read_error, read_to_string, sync_parent_dir, write_atomic,
+    ConfigurationError, Result,
+};
+
+/// A filesystem which configuration files can be read from and written to.
+///
+/// The loading functions use [StdFs], which accesses the real filesystem. Other implementations,
+/// such as an in-memory filesystem, can be passed to the functions ending in `_in`, e.g. to test
+/// code which loads configurations without touching the real filesystem.
+pub trait ConfigFs {
+    /// Read the file at `path` to a string.
+    ///
+    /// A missing file must be reported as [io::ErrorKind::NotFound].
+    fn read(&self, path: &Path) -> io::Result<String>;
+
+    /// Write `contents` to the file at `path`, replacing the file if it already exists.
+    fn write(&self, path: &Path, contents: &[u8]) -> io::Result<()>;
+
+    /// Check whether a file exists at `path`.
+    fn exists(&self, path: &Path) -> bool;
+
+    /// Flush the file or directory at `path` to durable storage, as done by
+    /// [load_or_write_default_durable_in] after writing a file.
+    ///
+    /// The default implementation does nothing, which suits filesystems without durable storage,
+    /// such as an in-memory filesystem.
+    fn sync(&self, path: &Path) -> io::Result<()> {
+        let _ = path;
+        Ok(())
+    }
+}
+
+/// The real filesystem, as accessed by the loading functions.
+///
+/// Files are read in the same way as by [load_from_path](crate::load_from_path), and written in
+/// the same way as by [save_to_path](crate::save_to_path): atomically, after creating any missing
+/// parent directories.
+#[derive(Clone, Copy, Debug, Default)]
+pub struct StdFs;
+
+impl ConfigFs for StdFs {
+    fn read(&self, path: &Path) -> io::Result<String> {
+        read_to_string::<Infallible>(path).map_err(io::Error::from)
+    }
+
+    fn write(&self, path: &Path, contents: &[u8]) -> io::Result<()> {
+        create_parent_dirs(path)?;
+        write_atomic(path, contents)
+    }
+
+    fn exists(&self, path: &Path) -> bool {
+        path.exists()
+    }
+
+    /// Flush the file or directory at `path` to disk. Directories are only flushed on Unix, and
+    /// are left as they are on other platforms, where they cannot be flushed.
+    fn sync(&self, path: &Path) -> io::Result<()> {
+        #[cfg(unix)]
+        return std::fs::File::open(path)?.sync_all();
+
+        #[cfg(not(unix))]
+        {
+            if path.is_dir() {
+                return Ok(());
+            }
+            std::fs::OpenOptions::new()
+                .write(true)
+                .open(path)?
+                .sync_all()
+        }
+    }
+}
+
+/// Load a configuration from the file at the given path in the given filesystem.
+///
+/// See [load_from_path](crate::load_from_path) for details.
+///
+/// # Examples
+///
+/// ```no_run
+/// use graze::StdFs;
+/// use serde::Deserialize;
+///
+/// #[derive(Deserialize)]
+/// struct Config {
+///     message: String
+/// }
+///
+/// let config: Config = graze::load_from_path_in(&StdFs, "Config.toml", |c| toml::from_str(c))
+///     .expect("Could not load configuration");
+/// ```
+pub fn load_from_path_in<V, P, T, E, D>(vfs: &V, path: P, deserializer: D) -> Result<T, E>
+where
+    V: ConfigFs + ?Sized,
+    P: AsRef<Path>,
+    D: FnOnce(&str) -> std::result::Result<T, E>,
+{
+    let read = |path: &Path| {
+        vfs.read(path).map_err(|err| {
+            debug!(
+                "Could not read configuration file '{}': {err}",
+                path.display()
+            );
+            read_error(path, err)
+        })
+    };
+
+    load_logged(path.as_ref(), read, deserializer)
+}
+
+/// Load a configuration from the file at `path`, whose content is returned by `read`, logging the
+/// time taken to deserialize it.
+pub(crate) fn load_logged<T, E, R, D>(path: &Path, read: R, deserializer: D) -> Result<T, E>
+where
+    R: FnOnce(&Path) -> Result<String, E>,
+    D: FnOnce(&str) -> std::result::Result<T, E>,
+{
+    trace!("Reading configuration file '{}'", path.display());
+    let content = read(path)?;
+
+    #[cfg(feature = "log")]
+    let start = std::time::Instant::now();
+
+    let config = load_from_str(&content, deserializer)?;
+    debug!(
+        "Deserialized configuration file '{}' in {:?}",
+        path.display(),
+        start.elapsed()
+    );
+
+    Ok(config)
+}
+
+/// Load a configuration from the file at the given path in the given filesystem, or use the
+/// default value if the file does not exist.
+///
+/// See [load_or_default](crate::load_or_default) for details.
+pub fn load_or_default_in<V, P, T, E, D, F>(
+    vfs: &V,
+    path: P,
+    deserializer: D,
+    default: F,
+) -> Result<T, E>
+where
+    V: ConfigFs + ?Sized,
+    P: AsRef<Path>,
+    D: FnOnce(&str) -> std::result::Result<T, E>,
+    F: FnOnce() -> T,
+{
+    match load_from_path_in(vfs, path, deserializer) {
+        Err(ConfigurationError::NotFound { .. }) => Ok(default()),
+        result => result,
+    }
+}
+
+/// Load a configuration from the file at the given path in the given filesystem, or write and use
+/// the default value if the file does not exist.
+///
+/// Unlike [load_or_write_default](crate::load_or_write_default), the filesystem is first asked
+/// whether the file exists, and the default value is then written using [ConfigFs::write]. A file
+/// created by another process in the meantime may therefore be replaced.
+pub fn load_or_write_default_in<V, P, T, E, D, S, F, B>(
+    vfs: &V,
+    path: P,
+    deserializer: D,
+    serializer: S,
+    default: F,
+) -> Result<T, E>
+where
+    V: ConfigFs + ?Sized,
+    P: AsRef<Path>,
+    D: FnOnce(&str) -> std::result::Result<T, E>,
+    S: FnOnce(&T) -> std::result::Result<B, E>,
+    B: AsRef<[u8]>,
+    F: FnOnce() -> T,
+{
+    let path = path.as_ref();
+
+    if vfs.exists(path) {
+        return load_from_path_in(vfs, path, deserializer);
+    }
+
+    let data = default();
+    let serialized = serializer(&data).map_err(ConfigurationError::serialize)?;
+    vfs.write(path, serialized.as_ref())
+        .map_err(|err| ConfigurationError::io(path, err))?;
+
+    Ok(data)
+}
+
+/// Load a configuration from the file at the given path in the given filesystem, or durably write
+/// and use the default value if the file does not exist.
+///
+/// This behaves like [load_or_write_default_in], except that the written file and the directory
+/// containing it are flushed using [ConfigFs::sync]. See
+/// [load_or_write_default_durable](crate::load_or_write_default_durable) for details.
+pub fn load_or_write_default_durable_in<V, P, T, E, D, S, F, B>(
+    vfs: &V,
+    path: P,
+    deserializer: D,
+    serializer: S,
+    default: F,
+) -> Result<T, E>
+where
+    V: ConfigFs + ?Sized,
+    P: AsRef<Path>,
+    D: FnOnce(&str) -> std::result::Result<T, E>,
+    S: FnOnce(&T) -> std::result::Result<B, E>,
+    B: AsRef<[u8]>,
+    F: FnOnce() -> T,
+{
+    let path = path.as_ref();
+
+    if vfs.exists(path) {
+        return load_from_path_in(vfs, path, deserializer);
+    }
+
+    let data = default();
+    let serialized = serializer(&data).map_err(ConfigurationError::serialize)?;
+    vfs.write(path, serialized.as_ref())
+        .and_then(|_| vfs.sync(path))
+        .and_then(|_| sync_parent_dir(vfs, path))
+        .map_err(|err| ConfigurationError::io(path, err))?;
+
+    Ok(data)
+}
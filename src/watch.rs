@@ -0,0 +1,182 @@
+//! Reloading configurations when their file changes, backed by [notify].
+
+use std::io;
+use std::path::{Path, PathBuf};
+use std::sync::mpsc::{self, Receiver, RecvTimeoutError};
+use std::sync::{Arc, Mutex};
+use std::thread;
+use std::time::Duration;
+
+use notify::{Event, EventKind, RecommendedWatcher, RecursiveMode, Watcher as _};
+
+use crate::{load_from_path, ConfigurationError, Result};
+
+/// How long the configuration file must go unchanged before it is reloaded.
+///
+/// Editors commonly write a file several times in quick succession when saving it; waiting for the
+/// writes to settle avoids reloading the configuration for each of them.
+const DEBOUNCE: Duration = Duration::from_millis(100);
+
+/// A handle to a configuration file being watched for changes, created by [watch_config].
+///
+/// Each time the file changes, it is reloaded and the result is delivered through [Watcher::recv]
+/// and its siblings. A reload which fails (e.g. because the file is being edited and is temporarily
+/// incorrect) is delivered as an error, and the watcher keeps running. The last successfully
+/// loaded configuration is available through [Watcher::latest].
+///
+/// The file stops being watched when the watcher is dropped.
+pub struct Watcher<T, E> {
+    _watcher: RecommendedWatcher,
+    receiver: Receiver<Result<T, E>>,
+    latest: Arc<Mutex<Option<T>>>,
+}
+
+impl<T, E> Watcher<T, E> {
+    /// Block until the configuration is reloaded, returning the result of the reload.
+    ///
+    /// Returns [None] if the watcher stopped running.
+    pub fn recv(&self) -> Option<Result<T, E>> {
+        self.receiver.recv().ok()
+    }
+
+    /// Block until the configuration is reloaded or the timeout elapses, returning the result of the
+    /// reload.
+    ///
+    /// Returns [None] if the timeout elapsed or the watcher stopped running.
+    pub fn recv_timeout(&self, timeout: Duration) -> Option<Result<T, E>> {
+        self.receiver.recv_timeout(timeout).ok()
+    }
+
+    /// Return the result of a pending reload, without blocking.
+    ///
+    /// Returns [None] if no reload is pending or the watcher stopped running.
+    pub fn try_recv(&self) -> Option<Result<T, E>> {
+        self.receiver.try_recv().ok()
+    }
+
+    /// Get the last configuration which was successfully reloaded, if any.
+    pub fn latest(&self) -> Option<T>
+    where
+        T: Clone,
+    {
+        self.latest
+            .lock()
+            .unwrap_or_else(|poisoned| poisoned.into_inner())
+            .clone()
+    }
+}
+
+/// Watch the configuration file at the given path, reloading it whenever it changes.
+///
+/// The directory containing the file is watched rather than the file itself, so the file does not
+/// need to exist yet, and editors which save by replacing the file are supported. Rapid successive
+/// changes are coalesced into a single reload.
+///
+/// # Examples
+///
+/// ```no_run
+/// use serde::Deserialize;
+///
+/// #[derive(Deserialize, Clone)]
+/// struct Config {
+///     message: String
+/// }
+///
+/// let watcher = graze::watch_config("Config.toml", |c| toml::from_str::<Config>(c))
+///     .expect("Could not watch configuration");
+///
+/// while let Some(result) = watcher.recv() {
+///     match result {
+///         Ok(config) => println!("{}", config.message),
+///         Err(err) => eprintln!("{err}"),
+///     }
+/// }
+/// ```
+pub fn watch_config<P, T, E, D>(path: P, deserializer: D) -> Result<Watcher<T, E>, E>
+where
+    P: AsRef<Path>,
+    T: Clone + Send + 'static,
+    E: Send + 'static,
+    D: Fn(&str) -> std::result::Result<T, E> + Send + 'static,
+{
+    let path = path.as_ref().to_path_buf();
+
+    let dir = match path.parent() {
+        Some(parent) if !parent.as_os_str().is_empty() => parent.to_path_buf(),
+        _ => PathBuf::from("."),
+    };
+
+    let (event_sender, events) = mpsc::channel();
+    let mut watcher = notify::recommended_watcher(move |event: notify::Result<Event>| {
+        let _ = event_sender.send(event);
+    })
+    .map_err(|err| to_io_error(&path, err))?;
+
+    watcher
+        .watch(&dir, RecursiveMode::NonRecursive)
+        .map_err(|err| to_io_error(&dir, err))?;
+
+    let (sender, receiver) = mpsc::channel();
+    let latest = Arc::new(Mutex::new(None));
+    let thread_latest = Arc::clone(&latest);
+
+    thread::spawn(move || {
+        while let Ok(event) = events.recv() {
+            if !affects(&event, &path) {
+                continue;
+            }
+
+            // Wait for the changes to settle, ignoring the events which arrive meanwhile.
+            loop {
+                match events.recv_timeout(DEBOUNCE) {
+                    Ok(_) => continue,
+                    Err(RecvTimeoutError::Timeout) => break,
+                    Err(RecvTimeoutError::Disconnected) => return,
+                }
+            }
+
+            let result = load_from_path(&path, &deserializer);
+
+            if let Ok(config) = &result {
+                *thread_latest
+                    .lock()
+                    .unwrap_or_else(|poisoned| poisoned.into_inner()) = Some(config.clone());
+            }
+
+            if sender.send(result).is_err() {
+                return;
+            }
+        }
+    });
+
+    Ok(Watcher {
+        _watcher: watcher,
+        receiver,
+        latest,
+    })
+}
+
+/// Check whether the given watcher event changes the file at `path`.
+fn affects(event: &notify::Result<Event>, path: &Path) -> bool {
+    let Ok(event) = event else {
+        return false;
+    };
+
+    let relevant = matches!(
+        event.kind,
+        EventKind::Any | EventKind::Create(_) | EventKind::Modify(_) | EventKind::Remove(_)
+    );
+    relevant
+        && event
+            .paths
+            .iter()
+            .any(|p| p.file_name() == path.file_name())
+}
+
+/// Convert a [notify::Error] which occurred while watching `path` into a [ConfigurationError::Io].
+fn to_io_error<E>(path: &Path, err: notify::Error) -> ConfigurationError<E> {
+    match err.kind {
+        notify::ErrorKind::Io(err) => ConfigurationError::io(path, err),
+        _ => ConfigurationError::io(path, io::Error::other(err)),
+    }
+}